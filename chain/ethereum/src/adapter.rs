@@ -18,7 +18,6 @@ use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::marker::Unpin;
 use thiserror::Error;
-use tiny_keccak::keccak256;
 use web3::types::{Address, Log, H256};
 
 use graph::prelude::*;
@@ -301,6 +300,10 @@ impl bc::TriggerFilter<Chain> for TriggerFilter {
         let EthereumBlockFilter {
             polling_intervals,
             contract_addresses: _contract_addresses,
+            function_selectors: _function_selectors,
+            // End blocks are not part of the filter; the block stream fetches
+            // them on the side, see `FirehoseMapper::required_blocks_between`
+            end_blocks: _end_blocks,
             trigger_every_block,
         } = self.block.clone();
 
@@ -623,6 +626,10 @@ pub struct EthereumCallFilter {
         HashMap<Address, (BlockNumber, HashSet<FunctionSelector>)>,
 
     pub wildcard_signatures: HashSet<FunctionSelector>,
+
+    /// Whether a call handler reads the trace ordinal of its calls. On RPC,
+    /// that takes all the traces of the blocks the calls are in
+    pub trace_ordinals: bool,
 }
 
 impl Into<Vec<CallToFilter>> for EthereumCallFilter {
@@ -634,6 +641,7 @@ impl Into<Vec<CallToFilter>> for EthereumCallFilter {
         let EthereumCallFilter {
             contract_addresses_function_signatures,
             wildcard_signatures,
+            trace_ordinals: _,
         } = self;
 
         let mut filters: Vec<CallToFilter> = contract_addresses_function_signatures
@@ -669,7 +677,8 @@ impl EthereumCallFilter {
 
         // The `call.input.len()` is validated in the
         // DataSource::match_and_decode function.
-        // Those calls are logged as warning and skipped.
+        // Those calls are logged as warning and skipped, or fail the
+        // subgraph for mappings with apiVersion >= 0.0.10.
         //
         // See 280b0108-a96e-4738-bb37-60ce11eeb5bf
         let call_signature = &call.input.0[..4];
@@ -698,20 +707,23 @@ impl EthereumCallFilter {
         let functions = mapping
             .call_handlers
             .iter()
-            .map(move |call_handler| {
-                let sig = keccak256(call_handler.function.as_bytes());
-                [sig[0], sig[1], sig[2], sig[3]]
-            })
+            .map(|call_handler| call_handler.selector)
             .collect();
 
         Self {
             wildcard_signatures: functions,
             contract_addresses_function_signatures: HashMap::new(),
+            trace_ordinals: mapping
+                .call_handlers
+                .iter()
+                .any(|call_handler| call_handler.trace_ordinal),
         }
     }
 
     pub fn from_data_sources<'a>(iter: impl IntoIterator<Item = &'a DataSource>) -> Self {
-        iter.into_iter()
+        let mut trace_ordinals = false;
+        let mut filter: Self = iter
+            .into_iter()
             .filter_map(|data_source| data_source.address.map(|addr| (addr, data_source)))
             .flat_map(|(contract_addr, data_source)| {
                 let start_block = data_source.start_block;
@@ -719,12 +731,15 @@ impl EthereumCallFilter {
                     .mapping
                     .call_handlers
                     .iter()
-                    .map(move |call_handler| {
-                        let sig = keccak256(call_handler.function.as_bytes());
-                        (start_block, contract_addr, [sig[0], sig[1], sig[2], sig[3]])
-                    })
+                    .map(move |call_handler| (start_block, contract_addr, call_handler))
+            })
+            .map(|(start_block, contract_addr, call_handler)| {
+                trace_ordinals |= call_handler.trace_ordinal;
+                (start_block, contract_addr, call_handler.selector)
             })
-            .collect()
+            .collect();
+        filter.trace_ordinals = trace_ordinals;
+        filter
     }
 
     /// Extends this call filter with another one.
@@ -736,6 +751,7 @@ impl EthereumCallFilter {
         let EthereumCallFilter {
             contract_addresses_function_signatures,
             wildcard_signatures,
+            trace_ordinals,
         } = other;
 
         // Extend existing address / function signature key pairs
@@ -759,6 +775,7 @@ impl EthereumCallFilter {
         }
 
         self.wildcard_signatures.extend(wildcard_signatures);
+        self.trace_ordinals |= trace_ordinals;
     }
 
     /// An empty filter is one that never matches.
@@ -767,6 +784,7 @@ impl EthereumCallFilter {
         let EthereumCallFilter {
             contract_addresses_function_signatures,
             wildcard_signatures: wildcard_matches,
+            trace_ordinals: _,
         } = self;
         contract_addresses_function_signatures.is_empty() && wildcard_matches.is_empty()
     }
@@ -794,6 +812,7 @@ impl FromIterator<(BlockNumber, Address, FunctionSelector)> for EthereumCallFilt
         EthereumCallFilter {
             contract_addresses_function_signatures: lookup,
             wildcard_signatures: HashSet::new(),
+            trace_ordinals: false,
         }
     }
 }
@@ -805,10 +824,16 @@ impl From<&EthereumBlockFilter> for EthereumCallFilter {
                 .contract_addresses
                 .iter()
                 .map(|(start_block_opt, address)| {
-                    (*address, (*start_block_opt, HashSet::default()))
+                    let selectors = ethereum_block_filter
+                        .function_selectors
+                        .get(address)
+                        .cloned()
+                        .unwrap_or_default();
+                    (*address, (*start_block_opt, selectors))
                 })
                 .collect::<HashMap<Address, (BlockNumber, HashSet<FunctionSelector>)>>(),
             wildcard_signatures: HashSet::new(),
+            trace_ordinals: false,
         }
     }
 }
@@ -818,19 +843,34 @@ pub struct EthereumBlockFilter {
     /// Used for polling block handlers, a hashset of (start_block, polling_interval)
     pub polling_intervals: HashSet<(BlockNumber, i32)>,
     pub contract_addresses: HashSet<(BlockNumber, Address)>,
+    /// Restricts the calls to an address in `contract_addresses` that
+    /// trigger a block to calls of these functions. Addresses without an
+    /// entry trigger on any call
+    pub function_selectors: HashMap<Address, HashSet<FunctionSelector>>,
+    /// End blocks of data sources with an end block handler
+    pub end_blocks: HashSet<BlockNumber>,
     pub trigger_every_block: bool,
 }
 
 impl Into<Vec<CallToFilter>> for EthereumBlockFilter {
     fn into(self) -> Vec<CallToFilter> {
-        self.contract_addresses
+        let EthereumBlockFilter {
+            contract_addresses,
+            function_selectors,
+            ..
+        } = self;
+
+        contract_addresses
             .into_iter()
             .map(|(_, addr)| addr)
             .sorted()
             .dedup_by(|x, y| x == y)
             .map(|addr| CallToFilter {
                 addresses: vec![addr.to_fixed_bytes().to_vec()],
-                signatures: vec![],
+                signatures: function_selectors
+                    .get(&addr)
+                    .map(|selectors| selectors.iter().sorted().map(|s| s.to_vec()).collect())
+                    .unwrap_or_default(),
             })
             .collect_vec()
     }
@@ -845,6 +885,8 @@ impl EthereumBlockFilter {
         Self {
             polling_intervals: HashSet::new(),
             contract_addresses: HashSet::new(),
+            function_selectors: HashMap::new(),
+            end_blocks: HashSet::new(),
             trigger_every_block: !mapping.block_handlers.is_empty(),
         }
     }
@@ -853,15 +895,17 @@ impl EthereumBlockFilter {
         iter.into_iter()
             .filter(|data_source| data_source.address.is_some())
             .fold(Self::default(), |mut filter_opt, data_source| {
-                let has_block_handler_with_call_filter = data_source
-                    .mapping
-                    .block_handlers
-                    .clone()
-                    .into_iter()
-                    .any(|block_handler| match block_handler.filter {
-                        Some(BlockHandlerFilter::Call) => true,
-                        _ => false,
-                    });
+                let call_filter_selectors =
+                    data_source
+                        .mapping
+                        .block_handlers
+                        .iter()
+                        .find_map(|block_handler| match &block_handler.filter {
+                            Some(BlockHandlerFilter::Call { selectors, .. }) => Some(selectors),
+                            _ => None,
+                        });
+                let has_block_handler_with_call_filter = call_filter_selectors.is_some();
+                let address = data_source.address.unwrap();
 
                 let has_block_handler_without_filter = data_source
                     .mapping
@@ -886,12 +930,23 @@ impl EthereumBlockFilter {
                         })
                         .collect(),
                     contract_addresses: if has_block_handler_with_call_filter {
-                        vec![(data_source.start_block, data_source.address.unwrap())]
+                        vec![(data_source.start_block, address)]
                             .into_iter()
                             .collect()
                     } else {
                         HashSet::default()
                     },
+                    function_selectors: match call_filter_selectors {
+                        Some(selectors) if !selectors.is_empty() => {
+                            HashMap::from_iter([(address, selectors.iter().copied().collect())])
+                        }
+                        _ => HashMap::new(),
+                    },
+                    end_blocks: data_source
+                        .end_block
+                        .filter(|_| data_source.mapping.end_block_handler.is_some())
+                        .into_iter()
+                        .collect(),
                 });
                 filter_opt
             })
@@ -905,15 +960,36 @@ impl EthereumBlockFilter {
         let EthereumBlockFilter {
             polling_intervals,
             contract_addresses,
+            function_selectors,
+            end_blocks,
             trigger_every_block,
         } = other;
 
         self.trigger_every_block = self.trigger_every_block || trigger_every_block;
+        self.end_blocks.extend(end_blocks);
 
         for other in contract_addresses {
             let (other_start_block, other_address) = other;
+            let existing = self.find_contract_address(&other_address);
+
+            // A block triggers for an address if a call matches either
+            // filter; if one of them accepts any call, so does the result
+            match (existing, function_selectors.get(&other_address).cloned()) {
+                (None, Some(selectors)) => {
+                    self.function_selectors.insert(other_address, selectors);
+                }
+                (None, None) => {}
+                (Some(_), Some(selectors)) => {
+                    if let Some(current) = self.function_selectors.get_mut(&other_address) {
+                        current.extend(selectors);
+                    }
+                }
+                (Some(_), None) => {
+                    self.function_selectors.remove(&other_address);
+                }
+            }
 
-            match self.find_contract_address(&other.1) {
+            match existing {
                 Some((current_start_block, current_address)) => {
                     if other_start_block < current_start_block {
                         self.contract_addresses
@@ -944,10 +1020,15 @@ impl EthereumBlockFilter {
         let Self {
             contract_addresses,
             polling_intervals,
+            function_selectors: _,
+            end_blocks,
             trigger_every_block,
         } = self;
         // If we are triggering every block, we are of course not empty
-        !*trigger_every_block && contract_addresses.is_empty() && polling_intervals.is_empty()
+        !*trigger_every_block
+            && contract_addresses.is_empty()
+            && polling_intervals.is_empty()
+            && end_blocks.is_empty()
     }
 
     fn find_contract_address(&self, candidate: &Address) -> Option<(i32, Address)> {
@@ -1321,6 +1402,7 @@ mod tests {
                     (address(2), (2, HashSet::new())),
                 ]),
                 wildcard_signatures: HashSet::new(),
+                trace_ordinals: false,
             },
             block: EthereumBlockFilter {
                 polling_intervals: HashSet::from_iter(vec![(1, 10), (3, 24)]),
@@ -1331,6 +1413,8 @@ mod tests {
                     (400, address(1000)),
                     (500, address(1000)),
                 ]),
+                function_selectors: HashMap::new(),
+                end_blocks: HashSet::new(),
                 trigger_every_block: false,
             },
         };
@@ -1443,10 +1527,13 @@ mod tests {
             call: EthereumCallFilter {
                 contract_addresses_function_signatures: HashMap::new(),
                 wildcard_signatures: HashSet::new(),
+                trace_ordinals: false,
             },
             block: EthereumBlockFilter {
                 polling_intervals: HashSet::default(),
                 contract_addresses: HashSet::new(),
+                function_selectors: HashMap::new(),
+                end_blocks: HashSet::new(),
                 trigger_every_block: true,
             },
         };
@@ -1497,6 +1584,48 @@ mod tests {
         assert_eq!(true, actual_send_all_block_headers);
     }
 
+    #[test]
+    fn ethereum_trigger_filter_to_firehose_end_blocks_plus_logfilter() {
+        let address = Address::from_low_u64_be;
+        let sig = H256::from_low_u64_le;
+        let mut filter = TriggerFilter {
+            log: EthereumLogFilter {
+                contracts_and_events_graph: GraphMap::new(),
+                wildcard_events: HashMap::new(),
+                events_with_topic_filters: HashMap::new(),
+            },
+            call: EthereumCallFilter {
+                contract_addresses_function_signatures: HashMap::new(),
+                wildcard_signatures: HashSet::new(),
+                trace_ordinals: false,
+            },
+            block: EthereumBlockFilter {
+                polling_intervals: HashSet::default(),
+                contract_addresses: HashSet::new(),
+                function_selectors: HashMap::new(),
+                end_blocks: HashSet::from_iter(vec![100, 200]),
+                trigger_every_block: false,
+            },
+        };
+
+        filter.log.contracts_and_events_graph.add_edge(
+            LogFilterNode::Contract(address(10)),
+            LogFilterNode::Event(sig(101)),
+            false,
+        );
+
+        let firehose_filter = filter.to_firehose_filter();
+        assert_eq!(1, firehose_filter.len());
+
+        let combined_filter = CombinedFilter::decode(&firehose_filter[0].value[..])
+            .expect("combined filter to decode");
+
+        // End blocks are fetched by the block stream, they must not turn on
+        // streaming every block header
+        assert_eq!(1, combined_filter.log_filters.len());
+        assert_eq!(false, combined_filter.send_all_block_headers);
+    }
+
     #[test]
     fn matching_ethereum_call_filter() {
         let call = |to: Address, input: Vec<u8>| EthereumCall {
@@ -1512,6 +1641,7 @@ mod tests {
                 (address(2), (2, HashSet::new())),
             ]),
             wildcard_signatures: HashSet::new(),
+            trace_ordinals: false,
         };
         let filter2 = EthereumCallFilter {
             contract_addresses_function_signatures: HashMap::from_iter(vec![(
@@ -1519,6 +1649,7 @@ mod tests {
                 (0, HashSet::from_iter(vec![[10u8; 4]])),
             )]),
             wildcard_signatures: HashSet::from_iter(vec![[11u8; 4]]),
+            trace_ordinals: false,
         };
 
         assert_eq!(
@@ -1601,12 +1732,16 @@ mod tests {
         let mut base = EthereumBlockFilter {
             polling_intervals: HashSet::new(),
             contract_addresses: HashSet::new(),
+            function_selectors: HashMap::new(),
+            end_blocks: HashSet::new(),
             trigger_every_block: false,
         };
 
         let extension = EthereumBlockFilter {
             polling_intervals: HashSet::from_iter(vec![(1, 3)]),
             contract_addresses: HashSet::from_iter(vec![(10, address(1))]),
+            function_selectors: HashMap::new(),
+            end_blocks: HashSet::new(),
             trigger_every_block: false,
         };
 
@@ -1625,12 +1760,16 @@ mod tests {
         let mut base = EthereumBlockFilter {
             polling_intervals: HashSet::from_iter(vec![(3, 3)]),
             contract_addresses: HashSet::from_iter(vec![(10, address(1))]),
+            function_selectors: HashMap::new(),
+            end_blocks: HashSet::new(),
             trigger_every_block: false,
         };
 
         let extension = EthereumBlockFilter {
             polling_intervals: HashSet::from_iter(vec![(2, 3), (3, 3)]),
             contract_addresses: HashSet::from_iter(vec![(2, address(1))]),
+            function_selectors: HashMap::new(),
+            end_blocks: HashSet::new(),
             trigger_every_block: false,
         };
 
@@ -1652,12 +1791,16 @@ mod tests {
         let mut base = EthereumBlockFilter {
             polling_intervals: HashSet::from_iter(vec![(2, 3)]),
             contract_addresses: HashSet::from_iter(vec![(2, address(1))]),
+            function_selectors: HashMap::new(),
+            end_blocks: HashSet::new(),
             trigger_every_block: false,
         };
 
         let extension = EthereumBlockFilter {
             polling_intervals: HashSet::from_iter(vec![(3, 3), (2, 3)]),
             contract_addresses: HashSet::from_iter(vec![(10, address(1))]),
+            function_selectors: HashMap::new(),
+            end_blocks: HashSet::new(),
             trigger_every_block: false,
         };
 
@@ -1679,12 +1822,16 @@ mod tests {
         let mut base = EthereumBlockFilter {
             polling_intervals: HashSet::new(),
             contract_addresses: HashSet::default(),
+            function_selectors: HashMap::new(),
+            end_blocks: HashSet::new(),
             trigger_every_block: false,
         };
 
         let extension = EthereumBlockFilter {
             polling_intervals: HashSet::new(),
             contract_addresses: HashSet::default(),
+            function_selectors: HashMap::new(),
+            end_blocks: HashSet::new(),
             trigger_every_block: true,
         };
 
@@ -1699,12 +1846,16 @@ mod tests {
         let mut base = EthereumBlockFilter {
             polling_intervals: HashSet::from_iter(vec![(10, 3)]),
             contract_addresses: HashSet::from_iter(vec![(10, address(2))]),
+            function_selectors: HashMap::new(),
+            end_blocks: HashSet::new(),
             trigger_every_block: true,
         };
 
         let extension = EthereumBlockFilter {
             polling_intervals: HashSet::new(),
             contract_addresses: HashSet::from_iter(vec![]),
+            function_selectors: HashMap::new(),
+            end_blocks: HashSet::new(),
             trigger_every_block: false,
         };
 
@@ -1723,12 +1874,16 @@ mod tests {
         let mut base = EthereumBlockFilter {
             polling_intervals: HashSet::from_iter(vec![(10, 3)]),
             contract_addresses: HashSet::from_iter(vec![(10, address(2))]),
+            function_selectors: HashMap::new(),
+            end_blocks: HashSet::new(),
             trigger_every_block: false,
         };
 
         let extension = EthereumBlockFilter {
             polling_intervals: HashSet::from_iter(vec![(10, 3)]),
             contract_addresses: HashSet::from_iter(vec![(10, address(1))]),
+            function_selectors: HashMap::new(),
+            end_blocks: HashSet::new(),
             trigger_every_block: true,
         };
 
@@ -1759,6 +1914,7 @@ mod tests {
                 ),
             ]),
             wildcard_signatures: HashSet::new(),
+            trace_ordinals: false,
         };
         let extension = EthereumCallFilter {
             contract_addresses_function_signatures: HashMap::from_iter(vec![
@@ -1772,6 +1928,7 @@ mod tests {
                 ),
             ]),
             wildcard_signatures: HashSet::new(),
+            trace_ordinals: false,
         };
         base.extend(extension);
 
@@ -1,5 +1,6 @@
 use std::{
     collections::HashMap,
+    mem,
     sync::{Arc, Mutex},
 };
 
@@ -8,36 +9,87 @@ use graph::{
     components::store::EthereumCallCache,
     data::store::ethereum::call,
     prelude::{BlockPtr, CachedEthereumCall},
+    prometheus::Counter,
     slog::{error, Logger},
+    tokio,
 };
 
+use crate::ENV_VARS;
+
 /// A wrapper around an Ethereum call cache that buffers call results in
 /// memory for the duration of a block. If `get_call` or `set_call` are
 /// called with a different block pointer than the one used in the previous
 /// call, the buffer is cleared.
+///
+/// Calls passed to `set_call` are also collected and written to the
+/// underlying cache with one `set_calls` once the subgraph is done with the
+/// block and calls `flush`, or earlier if more than
+/// `GRAPH_ETHEREUM_CALL_CACHE_FLUSH_SIZE` of them accumulate. Calls that
+/// were not flushed are written when the block changes or the cache is
+/// dropped.
 pub struct BufferedCallCache {
     call_cache: Arc<dyn EthereumCallCache>,
     buffer: Arc<Mutex<HashMap<call::Request, call::Retval>>>,
     block: Arc<Mutex<Option<BlockPtr>>>,
+    /// Calls made at `block` that have not been written to `call_cache`
+    unflushed: Arc<Mutex<Vec<(call::Request, call::Retval)>>>,
+    /// The number of unflushed calls at which they are written
+    flush_size: usize,
+    flush_failures: Counter,
+    logger: Logger,
 }
 
 impl BufferedCallCache {
-    pub fn new(call_cache: Arc<dyn EthereumCallCache>) -> Self {
+    pub fn new(
+        call_cache: Arc<dyn EthereumCallCache>,
+        flush_failures: Counter,
+        logger: Logger,
+    ) -> Self {
         Self {
             call_cache,
             buffer: Arc::new(Mutex::new(HashMap::new())),
             block: Arc::new(Mutex::new(None)),
+            unflushed: Arc::new(Mutex::new(Vec::new())),
+            flush_size: ENV_VARS.call_cache_flush_size,
+            flush_failures,
+            logger,
         }
     }
 
     fn check_block(&self, block: &BlockPtr) {
         let mut self_block = self.block.lock().unwrap();
         if self_block.as_ref() != Some(block) {
-            *self_block = Some(block.clone());
+            if let Some(prev_block) = self_block.replace(block.clone()) {
+                let calls = mem::take(&mut *self.unflushed.lock().unwrap());
+                self.write(prev_block, calls);
+            }
             self.buffer.lock().unwrap().clear();
         }
     }
 
+    /// Write `calls` that were made at `block` to the underlying cache in
+    /// the background. The cache is only an optimization, and failing to
+    /// write to it is logged and counted but does not fail the block
+    fn write(&self, block: BlockPtr, calls: Vec<(call::Request, call::Retval)>) {
+        if calls.is_empty() {
+            return;
+        }
+
+        let cache = self.call_cache.cheap_clone();
+        let logger = self.logger.cheap_clone();
+        let flush_failures = self.flush_failures.clone();
+        let _ = graph::spawn_blocking_allow_panic(move || {
+            let count = calls.len();
+            if let Err(e) = cache.set_calls(&logger, calls, block.cheap_clone()) {
+                flush_failures.inc();
+                error!(logger, "BufferedCallCache: call cache set error";
+                        "block_number" => block.number,
+                        "calls" => count,
+                        "error" => e.to_string());
+            }
+        });
+    }
+
     fn get(&self, call: &call::Request) -> Option<call::Response> {
         let buffer = self.buffer.lock().unwrap();
         buffer.get(call).map(|retval| {
@@ -120,26 +172,227 @@ impl EthereumCallCache for BufferedCallCache {
         self.check_block(&block);
 
         // Enter the call into the in-memory cache immediately so that
-        // handlers will find it, but add it to the underlying cache in the
-        // background so we do not have to wait for that as it will be a
+        // handlers will find it, but only add it to the underlying cache
+        // together with the other calls for this block since that is a
         // cache backed by the database
         {
             let mut buffer = self.buffer.lock().unwrap();
             buffer.insert(call.cheap_clone(), return_value.clone());
         }
 
-        let cache = self.call_cache.cheap_clone();
-        let logger = logger.cheap_clone();
-        let _ = graph::spawn_blocking_allow_panic(move || {
-            cache
-                .set_call(&logger, call.cheap_clone(), block, return_value)
-                .map_err(|e| {
-                    error!(logger, "BufferedCallCache: call cache set error";
-                            "contract_address" => format!("{:?}", call.address),
-                            "error" => e.to_string())
-                })
-        });
+        let calls = {
+            let mut unflushed = self.unflushed.lock().unwrap();
+            unflushed.push((call, return_value));
+            if unflushed.len() < self.flush_size {
+                return Ok(());
+            }
+            mem::take(&mut *unflushed)
+        };
+        self.write(block, calls);
 
         Ok(())
     }
+
+    fn set_calls(
+        &self,
+        logger: &Logger,
+        calls: Vec<(call::Request, call::Retval)>,
+        block: BlockPtr,
+    ) -> Result<(), graph::prelude::Error> {
+        for (call, return_value) in calls {
+            self.set_call(logger, call, block.cheap_clone(), return_value)?;
+        }
+        Ok(())
+    }
+
+    fn flush(&self, block: &BlockPtr) {
+        // Hold the lock on `block` so that calls for the next block can
+        // not sneak in
+        let self_block = self.block.lock().unwrap();
+        if self_block.as_ref() != Some(block) {
+            return;
+        }
+        let calls = mem::take(&mut *self.unflushed.lock().unwrap());
+        self.write(block.cheap_clone(), calls);
+    }
+}
+
+impl Drop for BufferedCallCache {
+    fn drop(&mut self) {
+        // Writing in the background needs a runtime, and there is no point
+        // in blocking the thread that drops the cache for it otherwise
+        if tokio::runtime::Handle::try_current().is_err() {
+            return;
+        }
+
+        let block = self.block.lock().unwrap().take();
+        let calls = mem::take(&mut *self.unflushed.lock().unwrap());
+        if let Some(block) = block {
+            self.write(block, calls);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use graph::{
+        data::store::scalar::Bytes,
+        prelude::{
+            web3::types::{H160, H256},
+            BlockNumber, Error,
+        },
+        slog::{o, Discard},
+    };
+
+    use super::*;
+
+    /// A call cache that records how many calls were written for which
+    /// block
+    #[derive(Default)]
+    struct Recorder {
+        written: Mutex<Vec<(BlockNumber, usize)>>,
+    }
+
+    impl Recorder {
+        /// Wait for `count` writes, which happen in the background, and
+        /// return all writes so far
+        async fn wait_for(&self, count: usize) -> Vec<(BlockNumber, usize)> {
+            for _ in 0..100 {
+                if self.written.lock().unwrap().len() >= count {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            let mut written = self.written.lock().unwrap().clone();
+            written.sort();
+            written
+        }
+    }
+
+    impl EthereumCallCache for Recorder {
+        fn get_call(
+            &self,
+            _: &call::Request,
+            _: BlockPtr,
+        ) -> Result<Option<call::Response>, Error> {
+            Ok(None)
+        }
+
+        fn get_calls(
+            &self,
+            reqs: &[call::Request],
+            _: BlockPtr,
+        ) -> Result<(Vec<call::Response>, Vec<call::Request>), Error> {
+            Ok((vec![], reqs.to_vec()))
+        }
+
+        fn get_calls_in_block(&self, _: BlockPtr) -> Result<Vec<CachedEthereumCall>, Error> {
+            Ok(vec![])
+        }
+
+        fn set_call(
+            &self,
+            _: &Logger,
+            _: call::Request,
+            _: BlockPtr,
+            _: call::Retval,
+        ) -> Result<(), Error> {
+            unreachable!("calls are written with `set_calls`")
+        }
+
+        fn set_calls(
+            &self,
+            _: &Logger,
+            calls: Vec<(call::Request, call::Retval)>,
+            block: BlockPtr,
+        ) -> Result<(), Error> {
+            self.written
+                .lock()
+                .unwrap()
+                .push((block.number, calls.len()));
+            Ok(())
+        }
+    }
+
+    fn buffered(recorder: &Arc<Recorder>) -> BufferedCallCache {
+        BufferedCallCache::new(
+            recorder.cheap_clone(),
+            Counter::new("flush_failures", "Counts failed flushes").unwrap(),
+            Logger::root(Discard, o!()),
+        )
+    }
+
+    fn block(number: BlockNumber) -> BlockPtr {
+        BlockPtr::from((H256::from_low_u64_be(number as u64), number))
+    }
+
+    /// Make `count` distinct calls at block `number`
+    fn set_calls(cache: &BufferedCallCache, number: BlockNumber, count: u8) {
+        let logger = Logger::root(Discard, o!());
+        for data in 0..count {
+            let call = call::Request::new(H160::from_low_u64_be(1), vec![data], 0);
+            let retval = call::Retval::Value(Bytes::from([data].as_slice()));
+            cache
+                .set_call(&logger, call, block(number), retval)
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn calls_are_written_when_the_block_is_done() {
+        let recorder = Arc::new(Recorder::default());
+        let cache = buffered(&recorder);
+
+        set_calls(&cache, 1, 3);
+        assert!(recorder.written.lock().unwrap().is_empty());
+
+        // Flushing another block leaves the calls alone
+        cache.flush(&block(2));
+        cache.flush(&block(1));
+        cache.flush(&block(1));
+        assert_eq!(vec![(1, 3)], recorder.wait_for(1).await);
+
+        // The calls for the block are also still in memory
+        let call = call::Request::new(H160::from_low_u64_be(1), vec![0], 0);
+        let resp = cache.get_call(&call, block(1)).unwrap().unwrap();
+        assert_eq!(call::Source::Memory, resp.source);
+    }
+
+    #[tokio::test]
+    async fn calls_are_written_when_the_block_changes() {
+        let recorder = Arc::new(Recorder::default());
+        let cache = buffered(&recorder);
+
+        set_calls(&cache, 1, 3);
+        set_calls(&cache, 2, 2);
+        assert_eq!(vec![(1, 3)], recorder.wait_for(1).await);
+
+        cache.get_calls(&[], block(3)).unwrap();
+        assert_eq!(vec![(1, 3), (2, 2)], recorder.wait_for(2).await);
+    }
+
+    #[tokio::test]
+    async fn calls_are_written_when_the_flush_size_is_reached() {
+        let recorder = Arc::new(Recorder::default());
+        let mut cache = buffered(&recorder);
+        cache.flush_size = 2;
+
+        set_calls(&cache, 1, 5);
+        assert_eq!(vec![(1, 2), (1, 2)], recorder.wait_for(2).await);
+
+        cache.flush(&block(1));
+        assert_eq!(vec![(1, 1), (1, 2), (1, 2)], recorder.wait_for(3).await);
+    }
+
+    #[tokio::test]
+    async fn calls_are_written_when_the_cache_is_dropped() {
+        let recorder = Arc::new(Recorder::default());
+        let cache = buffered(&recorder);
+
+        set_calls(&cache, 1, 3);
+        drop(cache);
+        assert_eq!(vec![(1, 3)], recorder.wait_for(1).await);
+    }
 }
@@ -486,7 +486,20 @@ impl Blockchain for Chain {
     }
 
     fn runtime(&self) -> anyhow::Result<(Arc<dyn RuntimeAdapterTrait<Self>>, Self::DecoderHook)> {
-        let call_cache = Arc::new(BufferedCallCache::new(self.call_cache.cheap_clone()));
+        let flush_failures = self
+            .registry
+            .global_counter_vec(
+                "ethereum_call_cache_flush_failures",
+                "Counts failed writes of buffered eth_call results to the call cache",
+                &["network"],
+            )?
+            .with_label_values(&[self.name.as_str()]);
+        let call_cache = Arc::new(BufferedCallCache::new(
+            self.call_cache.cheap_clone(),
+            flush_failures,
+            self.logger_factory
+                .component_logger("BufferedCallCache", None),
+        ));
         let chain_ident = self.chain_store.chain_identifier()?;
 
         let builder = self.runtime_adapter_builder.build(
@@ -944,4 +957,34 @@ impl FirehoseMapperTrait<Chain> for FirehoseMapper {
         self.block_ptr_for_number(logger, endpoint, final_block_number)
             .await
     }
+
+    async fn required_blocks_between(
+        &self,
+        logger: &Logger,
+        endpoint: &Arc<FirehoseEndpoint>,
+        after: BlockNumber,
+        before: BlockNumber,
+    ) -> Result<Vec<firehose::Response>, Error> {
+        // End blocks of data sources with an end block handler can't be
+        // expressed in the Firehose filter, so they are fetched one by one
+        let mut end_blocks: Vec<_> = self
+            .filter
+            .block
+            .end_blocks
+            .iter()
+            .copied()
+            .filter(|block_number| after < *block_number && *block_number < before)
+            .collect();
+        end_blocks.sort();
+
+        let mut responses = Vec::new();
+        for block_number in end_blocks {
+            responses.push(
+                endpoint
+                    .block_response_for_number::<codec::Block>(logger, block_number)
+                    .await?,
+            );
+        }
+        Ok(responses)
+    }
 }
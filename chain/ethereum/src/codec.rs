@@ -14,6 +14,7 @@ use graph::{
         LightEthereumBlock,
     },
 };
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::{convert::TryFrom, fmt::Debug};
 
@@ -44,15 +45,96 @@ impl From<&BigInt> for web3::types::U256 {
     }
 }
 
+/// Where a call sits in the call tree of its transaction
+struct CallPosition {
+    parent_index: u32,
+    depth: u32,
+    /// The position of the call among the calls made by its parent
+    sibling: usize,
+    /// The position of the call in the transaction in execution order,
+    /// which is the order of the call's `index`
+    ordinal: u32,
+}
+
+/// The positions of all calls in a transaction, keyed by the call's
+/// `index`. They are computed in a single pass over the calls so that
+/// converting all calls of a transaction does not rescan them for each call
+pub struct CallPositions(HashMap<u32, CallPosition>);
+
+impl CallPositions {
+    pub fn new(trace: &TransactionTrace) -> Self {
+        let mut calls: Vec<_> = trace.calls.iter().collect();
+        calls.sort_by_key(|call| call.index);
+
+        // The number of calls seen so far for each parent
+        let mut children: HashMap<u32, usize> = HashMap::new();
+        let positions = calls
+            .into_iter()
+            .enumerate()
+            .map(|(ordinal, call)| {
+                let sibling = if call.depth > 0 {
+                    let count = children.entry(call.parent_index).or_default();
+                    *count += 1;
+                    *count - 1
+                } else {
+                    0
+                };
+                let position = CallPosition {
+                    parent_index: call.parent_index,
+                    depth: call.depth,
+                    sibling,
+                    ordinal: ordinal as u32,
+                };
+                (call.index, position)
+            })
+            .collect();
+        Self(positions)
+    }
+}
+
 pub struct CallAt<'a> {
     call: &'a Call,
     block: &'a Block,
     trace: &'a TransactionTrace,
+    positions: &'a CallPositions,
 }
 
 impl<'a> CallAt<'a> {
-    pub fn new(call: &'a Call, block: &'a Block, trace: &'a TransactionTrace) -> Self {
-        Self { call, block, trace }
+    pub fn new(
+        call: &'a Call,
+        block: &'a Block,
+        trace: &'a TransactionTrace,
+        positions: &'a CallPositions,
+    ) -> Self {
+        Self {
+            call,
+            block,
+            trace,
+            positions,
+        }
+    }
+
+    /// Reconstructs the parity-style trace address of the call by walking
+    /// up its parents, so that calls are ordered the same way regardless of
+    /// whether they came from RPC traces or firehose.
+    fn trace_address(&self) -> Vec<usize> {
+        let mut address = Vec::with_capacity(self.call.depth as usize);
+        let mut position = self.positions.0.get(&self.call.index);
+        while let Some(call) = position.filter(|call| call.depth > 0) {
+            address.push(call.sibling);
+            position = self.positions.0.get(&call.parent_index);
+        }
+        address.reverse();
+        address
+    }
+
+    /// The position of the call in the transaction in execution order,
+    /// which is the order of the call's `index`
+    fn trace_ordinal(&self) -> u32 {
+        self.positions
+            .0
+            .get(&self.call.index)
+            .map_or(0, |position| position.ordinal)
     }
 }
 
@@ -75,6 +157,8 @@ impl<'a> TryInto<EthereumCall> for CallAt<'a> {
             block_number: self.block.number as i32,
             transaction_hash: Some(self.trace.hash.try_decode_proto("call transaction hash")?),
             transaction_index: self.trace.index as u64,
+            trace_address: self.trace_address(),
+            trace_ordinal: Some(self.trace_ordinal()),
         })
     }
 }
@@ -361,11 +445,12 @@ impl TryInto<EthereumBlockWithCalls> for &Block {
                 self.transaction_traces
                     .iter()
                     .flat_map(|trace| {
+                        let positions = CallPositions::new(trace);
                         trace
                             .calls
                             .iter()
                             .filter(|call| !call.status_reverted && !call.status_failed)
-                            .map(|call| CallAt::new(call, self, trace).try_into())
+                            .map(|call| CallAt::new(call, self, trace, &positions).try_into())
                             .collect::<Vec<Result<EthereumCall, Error>>>()
                     })
                     .collect::<Result<_, _>>()?,
@@ -506,7 +591,7 @@ mod test {
 
     use crate::codec::BlockHeader;
 
-    use super::Block;
+    use super::{Block, Call, CallAt, CallPositions, TransactionTrace};
 
     #[test]
     fn ensure_block_serialization() {
@@ -528,6 +613,66 @@ mod test {
             format!(r#"{{"block":{{"data":null,"timestamp":"{}"}}}}"#, now)
         );
     }
+
+    #[test]
+    fn call_trace_address() {
+        fn call(index: u32, parent_index: u32, depth: u32) -> Call {
+            Call {
+                index,
+                parent_index,
+                depth,
+                ..Default::default()
+            }
+        }
+
+        // root -> [a -> [c, d], b]
+        let trace = TransactionTrace {
+            calls: vec![
+                call(0, 0, 0),
+                call(1, 0, 1),
+                call(2, 1, 2),
+                call(3, 1, 2),
+                call(4, 0, 1),
+            ],
+            ..Default::default()
+        };
+        let block = Block::default();
+        let positions = CallPositions::new(&trace);
+
+        let trace_addresses: Vec<_> = trace
+            .calls
+            .iter()
+            .map(|call| CallAt::new(call, &block, &trace, &positions).trace_address())
+            .collect();
+
+        assert_eq!(
+            trace_addresses,
+            vec![vec![], vec![0], vec![0, 0], vec![0, 1], vec![1]]
+        );
+
+        // Positions do not depend on the order in which firehose lists calls
+        let mut reversed = trace.clone();
+        reversed.calls.reverse();
+        let positions = CallPositions::new(&reversed);
+        let positions: Vec<_> = reversed
+            .calls
+            .iter()
+            .map(|call| {
+                let call = CallAt::new(call, &block, &reversed, &positions);
+                (call.trace_address(), call.trace_ordinal())
+            })
+            .collect();
+        assert_eq!(
+            positions,
+            vec![
+                (vec![1], 4),
+                (vec![0, 1], 3),
+                (vec![0, 0], 2),
+                (vec![0], 1),
+                (vec![], 0)
+            ]
+        );
+    }
 }
 
 fn get_to_address(trace: &TransactionTrace) -> Result<Option<H160>, Error> {
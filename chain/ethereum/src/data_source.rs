@@ -1,9 +1,11 @@
 use anyhow::{anyhow, Error};
 use anyhow::{ensure, Context};
-use graph::blockchain::{BlockPtr, TriggerWithHandler};
+use graph::blockchain::{BlockPtr, DeclaredFileSource, TriggerWithHandler};
 use graph::components::metrics::subgraph::SubgraphInstanceMetrics;
 use graph::components::store::{EthereumCallCache, StoredDynamicDataSource};
-use graph::components::subgraph::{HostMetrics, InstanceDSTemplateInfo, MappingError};
+use graph::components::subgraph::{
+    DeterministicDecodeError, HostMetrics, InstanceDSTemplateInfo, MappingError,
+};
 use graph::components::trigger_processor::RunnableTriggers;
 use graph::data::value::Word;
 use graph::data_source::CausalityRegion;
@@ -11,6 +13,7 @@ use graph::env::ENV_VARS;
 use graph::futures03::future::try_join;
 use graph::futures03::stream::FuturesOrdered;
 use graph::futures03::TryStreamExt;
+use graph::ipfs::ContentPath;
 use graph::prelude::ethabi::ethereum_types::H160;
 use graph::prelude::ethabi::{StateMutability, Token};
 use graph::prelude::lazy_static;
@@ -21,7 +24,7 @@ use itertools::Itertools;
 use serde::de;
 use serde::de::Error as ErrorD;
 use serde::{Deserialize, Deserializer};
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::num::NonZeroU32;
 use std::str::FromStr;
 use std::sync::Arc;
@@ -42,11 +45,11 @@ use graph::{
 };
 
 use graph::data::subgraph::{
-    calls_host_fn, DataSourceContext, Source, MIN_SPEC_VERSION, SPEC_VERSION_0_0_8,
-    SPEC_VERSION_1_2_0,
+    calls_host_fn, DataSourceContext, Source, API_VERSION_0_0_10, MIN_SPEC_VERSION,
+    SPEC_VERSION_0_0_8, SPEC_VERSION_1_2_0, SPEC_VERSION_1_3_0,
 };
 
-use crate::adapter::EthereumAdapter as _;
+use crate::adapter::{EthereumAdapter as _, FunctionSelector};
 use crate::chain::Chain;
 use crate::network::EthereumNetworkAdapters;
 use crate::trigger::{EthereumBlockTriggerType, EthereumTrigger, MappingTrigger};
@@ -57,6 +60,7 @@ const ETHEREUM_KINDS: &[&str] = &["ethereum/contract", "ethereum"];
 const EVENT_HANDLER_KIND: &str = "event";
 const CALL_HANDLER_KIND: &str = "call";
 const BLOCK_HANDLER_KIND: &str = "block";
+const END_BLOCK_HANDLER_KIND: &str = "end_block";
 
 /// Runtime representation of a data source.
 // Note: Not great for memory usage that this needs to be `Clone`, considering how there may be tens
@@ -143,6 +147,19 @@ impl blockchain::DataSource<Chain> for DataSource {
             .any(|handler| !handler.calls.decls.is_empty())
     }
 
+    fn has_wildcard_event_handlers(&self) -> bool {
+        self.address.is_none() && !self.mapping.event_handlers.is_empty()
+    }
+
+    fn file_source_templates(&self) -> Vec<&str> {
+        self.mapping
+            .event_handlers
+            .iter()
+            .flat_map(|handler| &handler.file_sources)
+            .map(|decl| decl.template.as_str())
+            .collect()
+    }
+
     fn handler_kinds(&self) -> HashSet<&str> {
         let mut kinds = HashSet::new();
 
@@ -150,6 +167,7 @@ impl blockchain::DataSource<Chain> for DataSource {
             event_handlers,
             call_handlers,
             block_handlers,
+            end_block_handler,
             ..
         } = &self.mapping;
 
@@ -162,6 +180,9 @@ impl blockchain::DataSource<Chain> for DataSource {
         for handler in block_handlers.iter() {
             kinds.insert(handler.kind());
         }
+        if end_block_handler.is_some() {
+            kinds.insert(END_BLOCK_HANDLER_KIND);
+        }
 
         kinds
     }
@@ -233,6 +254,7 @@ impl blockchain::DataSource<Chain> for DataSource {
             && mapping.event_handlers == other.mapping.event_handlers
             && mapping.call_handlers == other.mapping.call_handlers
             && mapping.block_handlers == other.mapping.block_handlers
+            && mapping.end_block_handler == other.mapping.end_block_handler
             && context == &other.context
     }
 
@@ -305,7 +327,8 @@ impl blockchain::DataSource<Chain> for DataSource {
         // Validate that there is a `source` address if there are call or block handlers
         let no_source_address = self.address().is_none();
         let has_call_handlers = !self.mapping.call_handlers.is_empty();
-        let has_block_handlers = !self.mapping.block_handlers.is_empty();
+        let has_block_handlers =
+            !self.mapping.block_handlers.is_empty() || self.mapping.end_block_handler.is_some();
         if no_source_address && (has_call_handlers || has_block_handlers) {
             errors.push(SubgraphManifestValidationError::SourceAddressRequired.into());
         };
@@ -324,7 +347,7 @@ impl blockchain::DataSource<Chain> for DataSource {
                 match block_handler.filter {
                     None => non_filtered_block_handler_count += 1,
                     Some(ref filter) => match filter {
-                        BlockHandlerFilter::Call => call_filtered_block_handler_count += 1,
+                        BlockHandlerFilter::Call { .. } => call_filtered_block_handler_count += 1,
                         BlockHandlerFilter::Once => initialization_handler_count += 1,
                         BlockHandlerFilter::Polling { every: _ } => {
                             polling_filtered_block_handler_count += 1
@@ -357,6 +380,39 @@ impl blockchain::DataSource<Chain> for DataSource {
             errors.push(anyhow!("data source has duplicated block handlers"));
         }
 
+        // Validate that the functions in `call` filters of block handlers
+        // exist in the contract ABI
+        for handler in &self.mapping.block_handlers {
+            if let Some(BlockHandlerFilter::Call {
+                functions,
+                selectors,
+            }) = &handler.filter
+            {
+                for (function, selector) in functions.iter().zip(selectors) {
+                    if !self
+                        .contract_abi
+                        .contract
+                        .functions()
+                        .any(|f| &f.short_signature() == selector)
+                    {
+                        errors.push(anyhow!(
+                            "block handler {}: function `{}` not found in contract `{}`",
+                            handler.handler,
+                            function,
+                            self.contract_abi.name
+                        ));
+                    }
+                }
+            }
+        }
+
+        // An end block handler can only fire if the data source has an end block
+        if self.mapping.end_block_handler.is_some() && self.end_block.is_none() {
+            errors.push(anyhow!(
+                "data source has an `endBlockHandler` but no `endBlock`"
+            ));
+        }
+
         // Validate that event handlers don't require receipts for API versions lower than 0.0.7
         let api_version = self.api_version();
         if api_version < semver::Version::new(0, 0, 7) {
@@ -382,6 +438,27 @@ impl blockchain::DataSource<Chain> for DataSource {
             }
         }
 
+        for handler in &self.mapping.event_handlers {
+            if handler.file_sources.is_empty() {
+                continue;
+            }
+            match self.contract_event_with_signature(&handler.event) {
+                Some(event) => {
+                    for decl in &handler.file_sources {
+                        if let Err(e) = decl.validate(event) {
+                            errors.push(anyhow!("handler {}: {}", handler.handler, e));
+                        }
+                    }
+                }
+                None => errors.push(anyhow!(
+                    "handler {}: event `{}` not found in contract `{}`",
+                    handler.handler,
+                    handler.event,
+                    self.contract_abi.name
+                )),
+            }
+        }
+
         for handler in &self.mapping.event_handlers {
             for call in handler.calls.decls.as_ref() {
                 match self.mapping.find_abi(&call.expr.abi) {
@@ -409,18 +486,37 @@ impl blockchain::DataSource<Chain> for DataSource {
         let mut min_version = MIN_SPEC_VERSION;
 
         for handler in &self.mapping.block_handlers {
-            match handler.filter {
+            match &handler.filter {
                 Some(BlockHandlerFilter::Polling { every: _ }) | Some(BlockHandlerFilter::Once) => {
                     min_version = std::cmp::max(min_version, SPEC_VERSION_0_0_8);
                 }
+                Some(BlockHandlerFilter::Call { functions, .. }) if !functions.is_empty() => {
+                    min_version = std::cmp::max(min_version, SPEC_VERSION_1_3_0);
+                }
                 _ => {}
             }
         }
 
+        if self.mapping.end_block_handler.is_some() {
+            min_version = std::cmp::max(min_version, SPEC_VERSION_1_3_0);
+        }
+
+        if self
+            .mapping
+            .call_handlers
+            .iter()
+            .any(|handler| handler.trace_ordinal)
+        {
+            min_version = std::cmp::max(min_version, SPEC_VERSION_1_3_0);
+        }
+
         for handler in &self.mapping.event_handlers {
             if handler.has_additional_topics() {
                 min_version = std::cmp::max(min_version, SPEC_VERSION_1_2_0);
             }
+            if !handler.file_sources.is_empty() || handler.dedupe {
+                min_version = std::cmp::max(min_version, SPEC_VERSION_1_3_0);
+            }
         }
 
         min_version
@@ -462,12 +558,16 @@ impl DataSource {
         })
     }
 
-    fn handlers_for_log(&self, log: &Log) -> Vec<MappingEventHandler> {
+    pub(crate) fn handlers_for_log(&self, log: &Log) -> Vec<&MappingEventHandler> {
+        // Logs without topic0 should simply be skipped
+        let Some(topic0) = log.topics.first() else {
+            return vec![];
+        };
+
         self.mapping
             .event_handlers
-            .iter()
-            .filter(|handler| handler.matches(&log))
-            .cloned()
+            .for_topic0(topic0)
+            .filter(|handler| handler.matches(log))
             .collect::<Vec<_>>()
     }
 
@@ -481,49 +581,66 @@ impl DataSource {
 
         let target_method_id = &call.input.0[..4];
 
-        Ok(self.mapping.call_handlers.iter().find(move |handler| {
-            let fhash = keccak256(handler.function.as_bytes());
-            let actual_method_id = [fhash[0], fhash[1], fhash[2], fhash[3]];
-            target_method_id == actual_method_id
-        }))
+        Ok(self
+            .mapping
+            .call_handlers
+            .iter()
+            .find(move |handler| target_method_id == handler.selector))
     }
 
     fn handler_for_block(
         &self,
         trigger_type: &EthereumBlockTriggerType,
         block: BlockNumber,
-    ) -> Option<&MappingBlockHandler> {
+    ) -> Option<&str> {
         match trigger_type {
             // Start matches only initialization handlers with a `once` filter
-            EthereumBlockTriggerType::Start => {
-                self.mapping
-                    .block_handlers
-                    .iter()
-                    .find(move |handler| match handler.filter {
-                        Some(BlockHandlerFilter::Once) => block == self.start_block,
-                        _ => false,
-                    })
-            }
+            EthereumBlockTriggerType::Start => self
+                .mapping
+                .block_handlers
+                .iter()
+                .find(move |handler| match handler.filter {
+                    Some(BlockHandlerFilter::Once) => block == self.start_block,
+                    _ => false,
+                })
+                .map(|handler| handler.handler.as_str()),
             // End matches all handlers without a filter or with a `polling` filter
-            EthereumBlockTriggerType::End => {
-                self.mapping
-                    .block_handlers
-                    .iter()
-                    .find(move |handler| match handler.filter {
-                        Some(BlockHandlerFilter::Polling { every }) => {
-                            let start_block = self.start_block;
-                            let should_trigger = (block - start_block) % every.get() as i32 == 0;
-                            should_trigger
-                        }
-                        None => true,
-                        _ => false,
-                    })
-            }
-            EthereumBlockTriggerType::WithCallTo(_address) => self
+            EthereumBlockTriggerType::End => self
                 .mapping
                 .block_handlers
                 .iter()
-                .find(move |handler| handler.filter == Some(BlockHandlerFilter::Call)),
+                .find(move |handler| match handler.filter {
+                    Some(BlockHandlerFilter::Polling { every }) => {
+                        let start_block = self.start_block;
+                        let should_trigger = (block - start_block) % every.get() as i32 == 0;
+                        should_trigger
+                    }
+                    None => true,
+                    _ => false,
+                })
+                .map(|handler| handler.handler.as_str()),
+            // WithCallTo matches the handler with a `call` filter if one of
+            // the called functions is in its `functions`, or if it has none.
+            // Other data sources on the same address may have asked for
+            // different functions, so the trigger alone is not enough
+            EthereumBlockTriggerType::WithCallTo(_address, called) => self
+                .mapping
+                .block_handlers
+                .iter()
+                .find(move |handler| match &handler.filter {
+                    Some(BlockHandlerFilter::Call { selectors, .. }) => {
+                        selectors.is_empty()
+                            || selectors.iter().any(|selector| called.contains(selector))
+                    }
+                    _ => false,
+                })
+                .map(|handler| handler.handler.as_str()),
+            // DataSourceEnd matches the end block handler, at the end block only
+            EthereumBlockTriggerType::DataSourceEnd => self
+                .mapping
+                .end_block_handler
+                .as_deref()
+                .filter(|_| self.end_block == Some(block)),
         }
     }
 
@@ -630,31 +747,6 @@ impl DataSource {
             })
     }
 
-    fn contract_function_with_signature(&self, target_signature: &str) -> Option<&Function> {
-        self.contract_abi
-            .contract
-            .functions()
-            .filter(|function| match function.state_mutability {
-                StateMutability::Payable | StateMutability::NonPayable => true,
-                StateMutability::Pure | StateMutability::View => false,
-            })
-            .find(|function| {
-                // Construct the argument function signature:
-                // `address,uint256,bool`
-                let mut arguments = function
-                    .inputs
-                    .iter()
-                    .map(|input| format!("{}", input.kind))
-                    .collect::<Vec<String>>()
-                    .join(",");
-                // `address,uint256,bool)
-                arguments.push(')');
-                // `operation(address,uint256,bool)`
-                let actual_signature = vec![function.name.clone(), arguments].join("(");
-                target_signature == actual_signature
-            })
-    }
-
     fn matches_trigger_address(&self, trigger: &EthereumTrigger) -> bool {
         let Some(ds_address) = self.address else {
             // 'wildcard' data sources match any trigger address.
@@ -694,7 +786,7 @@ impl DataSource {
                     MappingTrigger::Block {
                         block: block.cheap_clone(),
                     },
-                    handler.handler.clone(),
+                    handler.to_owned(),
                     block.block_ptr(),
                     block.timestamp(),
                 )))
@@ -749,6 +841,11 @@ impl DataSource {
                                     "handler" => &event_handler.handler,
                                     "event" => &event_handler.event,
                                     "error" => format!("{}", e),
+                                    "topics" => format!("{:?}", log.topics),
+                                    "data" => truncated_hex(
+                                        &log.data.0,
+                                        ENV_VARS.mappings.decode_error_data_limit
+                                    ),
                                 );
                             })
                             .ok()
@@ -802,7 +899,17 @@ impl DataSource {
                     "transaction" => format!("{}", &transaction.hash),
                 });
                 let handler = event_handler.handler.clone();
-                let calls = DeclaredCall::new(&self.mapping, &event_handler, &log, &params)?;
+                let calls = DeclaredCall::new(&self.mapping, event_handler, &log, &params)?;
+                // Unusable parameter values are kept so that the host skips
+                // and counts them along with invalid sources
+                let file_sources = event_handler
+                    .file_sources
+                    .iter()
+                    .map(|decl| DeclaredFileSource {
+                        template: decl.template.clone(),
+                        source: decl.source(&params),
+                    })
+                    .collect();
                 Ok(Some(TriggerWithHandler::<Chain>::new_with_logging_extras(
                     MappingTrigger::Log {
                         block: block.cheap_clone(),
@@ -811,6 +918,7 @@ impl DataSource {
                         params,
                         receipt: receipt.map(|r| r.cheap_clone()),
                         calls,
+                        file_sources,
                     },
                     handler,
                     block.block_ptr(),
@@ -827,7 +935,8 @@ impl DataSource {
 
                 // Identify the function ABI in the contract
                 let function_abi = self
-                    .contract_function_with_signature(handler.function.as_str())
+                    .contract_abi
+                    .function_with_selector(&handler.selector)
                     .with_context(|| {
                         anyhow!(
                             "Function with the signature \"{}\" not found in \
@@ -846,13 +955,29 @@ impl DataSource {
                 let tokens = match function_abi.decode_input(&call.input.0[4..]).with_context(
                     || {
                         format!(
-                            "Generating function inputs for the call {:?} failed, raw input: {}",
-                            &function_abi,
-                            hex::encode(&call.input.0)
+                            "Generating function inputs for the call to `{}` failed, \
+                             expected inputs ({}), raw input: {}",
+                            function_abi.name,
+                            function_abi
+                                .inputs
+                                .iter()
+                                .map(|param| format!("{} {}", param.kind, param.name))
+                                .join(", "),
+                            truncated_hex(&call.input.0, ENV_VARS.mappings.decode_error_data_limit)
                         )
                     },
                 ) {
                     Ok(val) => val,
+                    // The selector matched, so the call was meant for this
+                    // function; calldata that doesn't fit its inputs is a
+                    // deterministic error rather than a reason to skip the call
+                    Err(err) if self.mapping.api_version >= API_VERSION_0_0_10 => {
+                        return Err(DeterministicDecodeError {
+                            handler: handler.handler.clone(),
+                            error: err,
+                        }
+                        .into());
+                    }
                     // See also 280b0108-a96e-4738-bb37-60ce11eeb5bf
                     Err(err) => {
                         warn!(logger, "Failed parsing inputs, skipping"; "error" => &err.to_string());
@@ -886,7 +1011,10 @@ impl DataSource {
                         format!(
                             "Decoding function outputs for the call {:?} failed, raw output: {}",
                             &function_abi,
-                            hex::encode(&call.output.0)
+                            truncated_hex(
+                                &call.output.0,
+                                ENV_VARS.mappings.decode_error_data_limit
+                            )
                         )
                     })?;
 
@@ -922,6 +1050,7 @@ impl DataSource {
                         call: call.cheap_clone(),
                         inputs,
                         outputs,
+                        trace_ordinal: call.trace_ordinal.filter(|_| handler.trace_ordinal),
                     },
                     handler.handler.clone(),
                     block.block_ptr(),
@@ -1080,14 +1209,14 @@ impl DecoderHook {
             .iter()
             .zip(metrics)
             .zip(calls)
-            .for_each(|(((_, source), metrics), call)| {
-                if source.observe() {
-                    metrics.observe_eth_call_execution_time(
-                        elapsed,
-                        &call.contract_name,
-                        &call.function.name,
-                    );
-                }
+            .for_each(|(((res, source), metrics), call)| {
+                metrics.observe_eth_call(
+                    &call.contract_name,
+                    &call.function.name,
+                    source,
+                    elapsed,
+                    res.is_none(),
+                );
             });
 
         let labels = results
@@ -1100,6 +1229,143 @@ impl DecoderHook {
     }
 }
 
+/// Hex encode at most `limit` bytes of `data` that could not be decoded so
+/// that they can be included in errors and logs
+pub(crate) fn truncated_hex(data: &[u8], limit: usize) -> String {
+    if data.len() <= limit {
+        format!("0x{}", hex::encode(data))
+    } else {
+        format!(
+            "0x{}... ({} of {} bytes)",
+            hex::encode(&data[..limit]),
+            limit,
+            data.len()
+        )
+    }
+}
+
+/// Find the triggers for the same log that duplicate a trigger of another
+/// data source, i.e., that run a handler with the same name for the same
+/// event. Of each set of duplicates, the trigger of the data source that
+/// was created first wins; static data sources are created before any
+/// dynamic data source, and ties go to the earlier trigger.
+///
+/// Each entry of `triggers` holds the event handler and creation block of
+/// the data source for one trigger, or `None` if the trigger is not for a
+/// log. Returns, for each entry, whether it is a duplicate.
+pub(crate) fn duplicate_log_triggers(
+    triggers: &[Option<(&MappingEventHandler, Option<BlockNumber>)>],
+) -> Vec<bool> {
+    let same_handler = |a: &MappingEventHandler, b: &MappingEventHandler| {
+        a.handler == b.handler && a.event == b.event
+    };
+
+    triggers
+        .iter()
+        .enumerate()
+        .map(|(idx, trigger)| {
+            let Some((handler, _)) = *trigger else {
+                return false;
+            };
+            let winner = triggers
+                .iter()
+                .enumerate()
+                .filter_map(|(other_idx, other)| {
+                    let (other_handler, creation_block) = (*other)?;
+                    same_handler(handler, other_handler).then_some((creation_block, other_idx))
+                })
+                .min()
+                .map(|(_, other_idx)| other_idx);
+            winner != Some(idx)
+        })
+        .collect()
+}
+
+/// The duplicate log triggers of a block, see `dedup_log_triggers`
+#[derive(Debug, Default, PartialEq)]
+struct DuplicateLogTriggers {
+    duplicates: usize,
+    suppressed: usize,
+    /// The names of the data sources with duplicate triggers
+    data_sources: BTreeSet<String>,
+}
+
+impl DuplicateLogTriggers {
+    /// Log one warning for all duplicates in the block, if there are any
+    fn log(&self, logger: &Logger, block_ptr: &BlockPtr) {
+        if self.duplicates == 0 {
+            return;
+        }
+        warn!(logger, "Logs are handled by the same handler in several data sources";
+            "block" => block_ptr.number,
+            "duplicates" => self.duplicates,
+            "suppressed" => self.suppressed,
+            "data_sources" => self.data_sources.iter().join(", "),
+        );
+    }
+}
+
+/// Remove the triggers for a log that duplicate the trigger of another data
+/// source (see `duplicate_log_triggers`) if their handler is declared with
+/// `dedupe: true`. Duplicates of other handlers are still run, but they are
+/// added to `found` so they can be logged since they are a common source of
+/// double counting.
+///
+/// Data sources created in a block process the block's triggers separately
+/// from the other data sources, so their duplicates in that block are not
+/// detected.
+fn dedup_log_triggers<'a>(
+    mut runnable: RunnableTriggers<'a, Chain>,
+    found: &mut DuplicateLogTriggers,
+) -> RunnableTriggers<'a, Chain> {
+    if runnable.hosted_triggers.len() < 2 {
+        return runnable;
+    }
+
+    let triggers: Vec<_> = runnable
+        .hosted_triggers
+        .iter()
+        .map(|hosted| {
+            let MappingTrigger::Log { log, .. } = hosted.mapping_trigger.trigger.as_onchain()?
+            else {
+                return None;
+            };
+            let ds = hosted.host.data_source().as_onchain()?;
+            let handler_name = hosted.mapping_trigger.handler_name();
+            let handler = ds
+                .mapping
+                .event_handlers
+                .iter()
+                .find(|handler| handler.handler == handler_name && handler.matches(log))?;
+            Some((handler, ds.creation_block))
+        })
+        .collect();
+    let duplicates = duplicate_log_triggers(&triggers);
+    let remove: Vec<_> = triggers
+        .iter()
+        .zip(&duplicates)
+        .map(|(trigger, duplicate)| {
+            *duplicate && trigger.map_or(false, |(handler, _)| handler.dedupe)
+        })
+        .collect();
+
+    for (hosted, duplicate) in runnable.hosted_triggers.iter().zip(&duplicates) {
+        if *duplicate {
+            found.duplicates += 1;
+            found
+                .data_sources
+                .insert(hosted.host.data_source().name().to_owned());
+        }
+    }
+    found.suppressed += remove.iter().filter(|remove| **remove).count();
+
+    let mut remove = remove.into_iter();
+    runnable
+        .hosted_triggers
+        .retain(|_| !remove.next().unwrap_or(false));
+    runnable
+}
+
 #[async_trait]
 impl blockchain::DecoderHook<Chain> for DecoderHook {
     async fn after_decode<'a>(
@@ -1153,6 +1419,13 @@ impl blockchain::DecoderHook<Chain> for DecoderHook {
             }
         }
 
+        let mut duplicates = DuplicateLogTriggers::default();
+        let runnables = runnables
+            .into_iter()
+            .map(|runnable| dedup_log_triggers(runnable, &mut duplicates))
+            .collect::<Vec<_>>();
+        duplicates.log(logger, block_ptr);
+
         if ENV_VARS.mappings.disable_declared_calls {
             return Ok(runnables);
         }
@@ -1214,6 +1487,10 @@ impl blockchain::DecoderHook<Chain> for DecoderHook {
 
         Ok(runnables)
     }
+
+    fn block_done(&self, block_ptr: &BlockPtr) {
+        self.call_cache.flush(block_ptr);
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Deserialize)]
@@ -1289,6 +1566,15 @@ impl blockchain::UnresolvedDataSourceTemplate<Chain> for UnresolvedDataSourceTem
             mapping,
         } = self;
 
+        // Data sources created from a template have no `endBlock`, so an
+        // end block handler in a template could never fire
+        if mapping.end_block_handler.is_some() {
+            return Err(anyhow!(
+                "data source template `{}` has an `endBlockHandler`, which only data sources with an `endBlock` support",
+                name
+            ));
+        }
+
         let mapping = mapping
             .resolve(resolver, logger)
             .await
@@ -1341,6 +1627,7 @@ pub struct UnresolvedMapping {
     pub call_handlers: Vec<MappingCallHandler>,
     #[serde(default)]
     pub event_handlers: Vec<MappingEventHandler>,
+    pub end_block_handler: Option<String>,
     pub file: Link,
 }
 
@@ -1353,7 +1640,10 @@ pub struct Mapping {
     pub abis: Vec<Arc<MappingABI>>,
     pub block_handlers: Vec<MappingBlockHandler>,
     pub call_handlers: Vec<MappingCallHandler>,
-    pub event_handlers: Vec<MappingEventHandler>,
+    pub event_handlers: EventHandlers,
+    /// Handler that is called once, after all other triggers, when the
+    /// data source reaches its `endBlock`.
+    pub end_block_handler: Option<String>,
     pub runtime: Arc<Vec<u8>>,
     pub link: Link,
 }
@@ -1370,7 +1660,7 @@ impl Mapping {
     pub fn has_block_handler_with_call_filter(&self) -> bool {
         self.block_handlers
             .iter()
-            .any(|handler| matches!(handler.filter, Some(BlockHandlerFilter::Call)))
+            .any(|handler| matches!(handler.filter, Some(BlockHandlerFilter::Call { .. })))
     }
 
     pub fn find_abi(&self, abi_name: &str) -> Result<Arc<MappingABI>, Error> {
@@ -1383,6 +1673,77 @@ impl Mapping {
     }
 }
 
+/// The event handlers of a mapping, together with their positions keyed by
+/// topic0, so that a log is only matched against the handlers for its event
+/// rather than all of them. Handlers that share a topic0 are kept in manifest
+/// order. The index is built when the handlers are set and can not get out of
+/// sync with them; data sources created from a template share the index of
+/// the template's mapping.
+#[derive(Clone, Debug, Default)]
+pub struct EventHandlers {
+    handlers: Vec<MappingEventHandler>,
+    by_topic0: Arc<HashMap<H256, Vec<usize>>>,
+}
+
+impl EventHandlers {
+    pub fn new(handlers: Vec<MappingEventHandler>) -> Self {
+        // Compute the topic0 of each event handler once here instead of
+        // every time a log is matched against the handler
+        let handlers = handlers
+            .into_iter()
+            .map(|handler| MappingEventHandler {
+                topic0: Some(handler.topic0()),
+                ..handler
+            })
+            .collect::<Vec<_>>();
+
+        let mut by_topic0: HashMap<H256, Vec<usize>> = HashMap::new();
+        for (idx, handler) in handlers.iter().enumerate() {
+            by_topic0.entry(handler.topic0()).or_default().push(idx);
+        }
+
+        EventHandlers {
+            handlers,
+            by_topic0: Arc::new(by_topic0),
+        }
+    }
+
+    fn for_topic0<'a>(
+        &'a self,
+        topic0: &H256,
+    ) -> impl Iterator<Item = &'a MappingEventHandler> + 'a {
+        self.by_topic0
+            .get(topic0)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .map(move |idx| &self.handlers[*idx])
+    }
+}
+
+impl std::ops::Deref for EventHandlers {
+    type Target = [MappingEventHandler];
+
+    fn deref(&self) -> &Self::Target {
+        &self.handlers
+    }
+}
+
+impl<'a> IntoIterator for &'a EventHandlers {
+    type Item = &'a MappingEventHandler;
+    type IntoIter = std::slice::Iter<'a, MappingEventHandler>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.handlers.iter()
+    }
+}
+
+impl PartialEq for EventHandlers {
+    fn eq(&self, other: &Self) -> bool {
+        self.handlers == other.handlers
+    }
+}
+
 impl UnresolvedMapping {
     pub async fn resolve(
         self,
@@ -1398,6 +1759,7 @@ impl UnresolvedMapping {
             block_handlers,
             call_handlers,
             event_handlers,
+            end_block_handler,
             file: link,
         } = self;
 
@@ -1429,7 +1791,8 @@ impl UnresolvedMapping {
             abis,
             block_handlers: block_handlers.clone(),
             call_handlers: call_handlers.clone(),
-            event_handlers: event_handlers.clone(),
+            event_handlers: EventHandlers::new(event_handlers),
+            end_block_handler,
             runtime,
             link,
         })
@@ -1455,10 +1818,7 @@ impl UnresolvedMappingABI {
             )
         })?;
         let contract = Contract::load(&*contract_bytes)?;
-        Ok(MappingABI {
-            name: self.name,
-            contract,
-        })
+        Ok(MappingABI::new(self.name, contract))
     }
 }
 
@@ -1466,9 +1826,37 @@ impl UnresolvedMappingABI {
 pub struct MappingABI {
     pub name: String,
     pub contract: Contract,
+    /// The state-changing functions of `contract` by their selector, so
+    /// that call handlers can find the function for a call without hashing
+    /// the signatures of all functions
+    functions_by_selector: HashMap<FunctionSelector, Function>,
 }
 
 impl MappingABI {
+    pub fn new(name: String, contract: Contract) -> Self {
+        let functions_by_selector = contract
+            .functions()
+            .filter(|function| match function.state_mutability {
+                StateMutability::Payable | StateMutability::NonPayable => true,
+                StateMutability::Pure | StateMutability::View => false,
+            })
+            .map(|function| (function.short_signature(), function.clone()))
+            .collect();
+
+        MappingABI {
+            name,
+            contract,
+            functions_by_selector,
+        }
+    }
+
+    /// Returns the state-changing function whose selector is `selector`.
+    /// Overloaded functions have distinct selectors, so this always picks
+    /// the variant a call handler was declared for.
+    pub fn function_with_selector(&self, selector: &FunctionSelector) -> Option<&Function> {
+        self.functions_by_selector.get(selector)
+    }
+
     pub fn function(
         &self,
         contract_name: &str,
@@ -1522,7 +1910,7 @@ impl MappingBlockHandler {
     pub fn kind(&self) -> &str {
         match &self.filter {
             Some(filter) => match filter {
-                BlockHandlerFilter::Call => "block_filter_call",
+                BlockHandlerFilter::Call { .. } => "block_filter_call",
                 BlockHandlerFilter::Once => "block_filter_once",
                 BlockHandlerFilter::Polling { .. } => "block_filter_polling",
             },
@@ -1532,21 +1920,105 @@ impl MappingBlockHandler {
 }
 
 #[derive(Clone, Debug, Hash, Eq, PartialEq, Deserialize)]
-#[serde(tag = "kind", rename_all = "lowercase")]
+#[serde(from = "UnresolvedBlockHandlerFilter")]
 pub enum BlockHandlerFilter {
     // Call filter will trigger on all blocks where the data source contract
-    // address has been called
-    Call,
+    // address has been called. If `functions` is not empty, only calls to
+    // one of these functions, given by their full signature, count. The
+    // `selectors` of `functions` are computed once when the filter is loaded
+    Call {
+        functions: Vec<String>,
+        selectors: Vec<FunctionSelector>,
+    },
     // This filter will trigger once at the startBlock
     Once,
     // This filter will trigger in a recurring interval set by the `every` field.
-    Polling { every: NonZeroU32 },
+    Polling {
+        every: NonZeroU32,
+    },
+}
+
+impl BlockHandlerFilter {
+    pub fn call(functions: Vec<String>) -> Self {
+        let selectors = functions
+            .iter()
+            .map(|function| function_selector(function))
+            .collect();
+        BlockHandlerFilter::Call {
+            functions,
+            selectors,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+enum UnresolvedBlockHandlerFilter {
+    Call {
+        #[serde(default)]
+        functions: Vec<String>,
+    },
+    Once,
+    Polling {
+        every: NonZeroU32,
+    },
+}
+
+impl From<UnresolvedBlockHandlerFilter> for BlockHandlerFilter {
+    fn from(filter: UnresolvedBlockHandlerFilter) -> Self {
+        match filter {
+            UnresolvedBlockHandlerFilter::Call { functions } => BlockHandlerFilter::call(functions),
+            UnresolvedBlockHandlerFilter::Once => BlockHandlerFilter::Once,
+            UnresolvedBlockHandlerFilter::Polling { every } => {
+                BlockHandlerFilter::Polling { every }
+            }
+        }
+    }
 }
 
 #[derive(Clone, Debug, Hash, Eq, PartialEq, Deserialize)]
+#[serde(from = "UnresolvedMappingCallHandler")]
 pub struct MappingCallHandler {
     pub function: String,
     pub handler: String,
+    /// The first four bytes of the hash of `function`, computed once when
+    /// the handler is loaded so that matching calls never has to hash or
+    /// format signatures
+    pub selector: FunctionSelector,
+    /// Whether the handler reads the trace ordinal of its calls. On RPC,
+    /// computing it takes all the traces of the block, so it is only
+    /// provided to handlers that ask for it
+    pub trace_ordinal: bool,
+}
+
+impl MappingCallHandler {
+    pub fn new(function: String, handler: String) -> Self {
+        let selector = function_selector(&function);
+        Self {
+            function,
+            handler,
+            selector,
+            trace_ordinal: false,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UnresolvedMappingCallHandler {
+    function: String,
+    handler: String,
+    #[serde(default)]
+    trace_ordinal: bool,
+}
+
+impl From<UnresolvedMappingCallHandler> for MappingCallHandler {
+    fn from(handler: UnresolvedMappingCallHandler) -> Self {
+        MappingCallHandler {
+            trace_ordinal: handler.trace_ordinal,
+            ..MappingCallHandler::new(handler.function, handler.handler)
+        }
+    }
 }
 
 #[derive(Clone, Debug, Hash, Eq, PartialEq, Deserialize)]
@@ -1564,6 +2036,10 @@ pub struct MappingEventHandler {
     pub receipt: bool,
     #[serde(default)]
     pub calls: CallDecls,
+    #[serde(default, rename = "fileSources")]
+    pub file_sources: Vec<FileSourceDecl>,
+    #[serde(default)]
+    pub dedupe: bool,
 }
 
 // Custom deserializer for H256 fields that removes the '0x' prefix before parsing
@@ -1626,6 +2102,97 @@ impl MappingEventHandler {
     }
 }
 
+/// A file data source that the node creates from an event parameter after
+/// the event handler has run. In the manifest that's written as part of an
+/// event handler as
+/// ```yaml
+/// fileSources:
+///   - template: Metadata
+///     param: digest
+///     transform: cidv0FromBytes32
+/// ```
+#[derive(Clone, Debug, Hash, Eq, PartialEq, Deserialize)]
+pub struct FileSourceDecl {
+    /// The name of the file data source template
+    pub template: String,
+    /// The name of the event parameter that holds the source
+    pub param: String,
+    #[serde(default)]
+    pub transform: FileSourceTransform,
+}
+
+/// How the value of an event parameter is turned into the source of a file
+/// data source
+#[derive(Clone, Copy, Debug, Default, Hash, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FileSourceTransform {
+    /// Use a `string` parameter, or the UTF-8 contents of a `bytes`
+    /// parameter, as is
+    #[default]
+    Raw,
+    /// Use a `bytes32` parameter as the sha2-256 digest of a CIDv0
+    Cidv0FromBytes32,
+}
+
+impl FileSourceDecl {
+    fn validate(&self, event: &Event) -> Result<(), Error> {
+        let param = event
+            .inputs
+            .iter()
+            .find(|param| param.name == self.param)
+            .ok_or_else(|| {
+                anyhow!(
+                    "file source `{}`: event `{}` has no parameter `{}`",
+                    self.template,
+                    event.name,
+                    self.param
+                )
+            })?;
+
+        let valid = match self.transform {
+            // Indexed `string` and `bytes` parameters only hold a hash of
+            // the value
+            FileSourceTransform::Raw => {
+                !param.indexed && matches!(param.kind, ParamType::String | ParamType::Bytes)
+            }
+            FileSourceTransform::Cidv0FromBytes32 => param.kind == ParamType::FixedBytes(32),
+        };
+        ensure!(
+            valid,
+            "file source `{}`: parameter `{}` of event `{}` can not be used with the `{:?}` transform",
+            self.template,
+            self.param,
+            event.name,
+            self.transform
+        );
+        Ok(())
+    }
+
+    /// The source for the file data source, or `None` if the parameter
+    /// does not hold a value that can be turned into one.
+    fn source(&self, params: &[LogParam]) -> Option<String> {
+        let value = &params.iter().find(|param| param.name == self.param)?.value;
+        match (self.transform, value) {
+            (FileSourceTransform::Raw, Token::String(s)) => Some(s.clone()),
+            (FileSourceTransform::Raw, Token::Bytes(bytes)) => {
+                String::from_utf8(bytes.clone()).ok()
+            }
+            (FileSourceTransform::Cidv0FromBytes32, Token::FixedBytes(bytes)) => {
+                let digest = <[u8; 32]>::try_from(bytes.as_slice()).ok()?;
+                Some(ContentPath::from_sha256_digest(&digest).to_string())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Returns the 4-byte selector for a function signature like
+/// `transfer(address,uint256)`
+fn function_selector(signature: &str) -> FunctionSelector {
+    let hash = keccak256(signature.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
 /// Hashes a string to a H256 hash.
 fn string_to_h256(s: &str) -> H256 {
     let mut result = [0u8; 32];
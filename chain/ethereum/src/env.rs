@@ -88,6 +88,13 @@ pub struct EnvVars {
     /// This is a comma separated list of chain ids for which the gas field will not be set
     /// when calling `eth_call`.
     pub eth_call_no_gas: Vec<String>,
+    /// The number of `eth_call` results a subgraph collects before writing
+    /// them to the call cache in one go; whatever is left is written once
+    /// the subgraph is done with the block.
+    ///
+    /// Set by the environment variable `GRAPH_ETHEREUM_CALL_CACHE_FLUSH_SIZE`.
+    /// The default value is 1000.
+    pub call_cache_flush_size: usize,
 }
 
 // This does not print any values avoid accidentally leaking any sensitive env vars
@@ -137,6 +144,7 @@ impl From<Inner> for EnvVars {
                 .filter(|s| !s.is_empty())
                 .map(str::to_string)
                 .collect(),
+            call_cache_flush_size: x.call_cache_flush_size.max(1),
         }
     }
 }
@@ -186,4 +194,6 @@ struct Inner {
     genesis_block_number: u64,
     #[envconfig(from = "GRAPH_ETH_CALL_NO_GAS", default = "421613,421614")]
     eth_call_no_gas: String,
+    #[envconfig(from = "GRAPH_ETHEREUM_CALL_CACHE_FLUSH_SIZE", default = "1000")]
+    call_cache_flush_size: usize,
 }
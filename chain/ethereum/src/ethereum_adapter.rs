@@ -6,6 +6,7 @@ use graph::components::transaction_receipt::LightTransactionReceipt;
 use graph::data::store::ethereum::call;
 use graph::data::store::scalar;
 use graph::data::subgraph::UnifiedMappingApiVersion;
+use graph::data::subgraph::API_VERSION_0_0_10;
 use graph::data::subgraph::API_VERSION_0_0_7;
 use graph::futures01::stream;
 use graph::futures01::Future;
@@ -42,7 +43,7 @@ use graph::{
     components::ethereum::*,
     prelude::web3::api::Web3,
     prelude::web3::transports::Batch,
-    prelude::web3::types::{Trace, TraceFilter, TraceFilterBuilder, H160},
+    prelude::web3::types::{Action, Trace, TraceFilter, TraceFilterBuilder, H160},
 };
 use itertools::Itertools;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
@@ -55,14 +56,15 @@ use std::time::Instant;
 use crate::adapter::EthereumRpcError;
 use crate::adapter::ProviderStatus;
 use crate::chain::BlockFinality;
-use crate::trigger::LogRef;
+use crate::data_source::truncated_hex;
+use crate::trigger::{transaction_log_index, LogRef};
 use crate::Chain;
 use crate::NodeCapabilities;
 use crate::{
     adapter::{
         ContractCall, ContractCallError, EthGetLogsFilter, EthereumAdapter as EthereumAdapterTrait,
-        EthereumBlockFilter, EthereumCallFilter, EthereumLogFilter, ProviderEthRpcMetrics,
-        SubgraphEthRpcMetrics,
+        EthereumBlockFilter, EthereumCallFilter, EthereumLogFilter, FunctionSelector,
+        ProviderEthRpcMetrics, SubgraphEthRpcMetrics,
     },
     transport::Transport,
     trigger::{EthereumBlockTriggerType, EthereumTrigger},
@@ -563,18 +565,14 @@ impl EthereumAdapter {
             .compat()
     }
 
+    /// Returns the output of the call, or the reason why it reverted
     async fn call(
         &self,
         logger: Logger,
         call_data: call::Request,
         block_ptr: BlockPtr,
         gas: Option<u32>,
-    ) -> Result<call::Retval, ContractCallError> {
-        fn reverted(logger: &Logger, reason: &str) -> Result<call::Retval, ContractCallError> {
-            info!(logger, "Contract call reverted"; "reason" => reason);
-            Ok(call::Retval::Null)
-        }
-
+    ) -> Result<Result<scalar::Bytes, String>, ContractCallError> {
         let web3 = self.web3.clone();
         let logger = Logger::new(&logger, o!("provider" => self.provider.clone()));
 
@@ -650,28 +648,29 @@ impl EthereumAdapter {
                         .copied()
                         .chain(env_geth_call_errors.map(|s| s.as_str()));
 
-                    let as_solidity_revert_with_reason = |bytes: &[u8]| {
-                        let solidity_revert_function_selector =
-                            &tiny_keccak::keccak256(b"Error(string)")[..4];
-
-                        match bytes.len() >= 4 && &bytes[..4] == solidity_revert_function_selector {
-                            false => None,
-                            true => ethabi::decode(&[ParamType::String], &bytes[4..])
-                                .ok()
-                                .and_then(|tokens| tokens[0].clone().into_string()),
-                        }
-                    };
-
                     match result {
                         // A successful response.
-                        Ok(bytes) => Ok(call::Retval::Value(scalar::Bytes::from(bytes))),
+                        Ok(bytes) => Ok(Ok(scalar::Bytes::from(bytes))),
 
                         // Check for Geth revert.
                         Err(web3::Error::Rpc(rpc_error))
                             if geth_execution_errors
                                 .any(|e| rpc_error.message.to_lowercase().contains(e)) =>
                         {
-                            reverted(&logger, &rpc_error.message)
+                            // Newer Geth versions return the revert data
+                            // alongside the message.
+                            let reason = rpc_error
+                                .data
+                                .as_ref()
+                                .and_then(|d| d.as_str())
+                                .and_then(|d| hex::decode(d.trim_start_matches("0x")).ok())
+                                .and_then(|data| decode_revert_reason(&data));
+                            match reason {
+                                Some(reason) => {
+                                    Ok(Err(format!("{}: {}", rpc_error.message, reason)))
+                                }
+                                None => Ok(Err(rpc_error.message)),
+                            }
                         }
 
                         // Check for Parity revert.
@@ -694,12 +693,10 @@ impl EthereumAdapter {
                                         let payload = data.trim_start_matches(PARITY_REVERT_PREFIX);
                                         hex::decode(payload)
                                             .ok()
-                                            .and_then(|payload| {
-                                                as_solidity_revert_with_reason(&payload)
-                                            })
+                                            .and_then(|payload| decode_revert_reason(&payload))
                                             .unwrap_or("no reason".to_owned())
                                     };
-                                    reverted(&logger, &reason)
+                                    Ok(Err(reason))
                                 }
 
                                 // The VM execution error was not identified as a revert.
@@ -726,14 +723,24 @@ impl EthereumAdapter {
         req: call::Request,
         cache: Arc<dyn EthereumCallCache>,
     ) -> Result<call::Response, ContractCallError> {
-        let result = self
+        let result = match self
             .call(
                 logger.clone(),
                 req.cheap_clone(),
                 call.block_ptr.clone(),
                 call.gas,
             )
-            .await?;
+            .await?
+        {
+            Ok(output) => call::Retval::Value(output),
+            Err(reason) => {
+                info!(logger, "Contract call reverted";
+                    "reason" => reason,
+                    "function" => call.function.signature(),
+                    "address" => format!("{:?}", call.address));
+                call::Retval::Null
+            }
+        };
         let _ = cache
             .set_call(
                 &logger,
@@ -888,6 +895,7 @@ impl EthereumAdapter {
         let EthereumCallFilter {
             contract_addresses_function_signatures,
             wildcard_signatures,
+            trace_ordinals: _,
         } = call_filter;
 
         let mut addresses: Vec<H160> = contract_addresses_function_signatures
@@ -1039,10 +1047,35 @@ impl EthereumAdapter {
             ));
         }
 
-        Ok(traces
+        let mut calls: Vec<_> = traces
             .iter()
             .filter_map(EthereumCall::try_from_trace)
-            .collect())
+            .collect();
+        set_trace_ordinals(calls.iter_mut(), &traces);
+        Ok(calls)
+    }
+
+    /// Fills in the `trace_ordinal` of calls that were found with an
+    /// address-filtered `trace_filter`, which needs all the traces of the
+    /// blocks they are in. The traces of several blocks are fetched at once
+    pub(crate) async fn add_trace_ordinals(
+        &self,
+        logger: &Logger,
+        subgraph_metrics: Arc<SubgraphEthRpcMetrics>,
+        mut calls: Vec<EthereumCall>,
+    ) -> Result<Vec<EthereumCall>, Error> {
+        let blocks: BTreeSet<BlockNumber> = calls.iter().map(|call| call.block_number).collect();
+        let traces: Vec<Vec<Trace>> = futures03::stream::iter(blocks.into_iter().map(|block| {
+            self.clone()
+                .trace_stream(logger, subgraph_metrics.cheap_clone(), block, block, vec![])
+                .collect()
+                .compat()
+        }))
+        .buffered(ENV_VARS.block_ingestor_max_concurrent_json_rpc_calls)
+        .try_collect()
+        .await?;
+        set_trace_ordinals(calls.iter_mut(), &traces.concat());
+        Ok(calls)
     }
 
     /// Reorg safety: `to` must be a final block.
@@ -1566,7 +1599,10 @@ impl EthereumAdapterTrait for EthereumAdapter {
                         // Decode failures are reverts. The reasoning is that if Solidity fails to
                         // decode an argument, that's a revert, so the same goes for the output.
                         let reason = format!("failed to decode output: {}", e);
-                        info!(logger, "Contract call reverted"; "reason" => reason);
+                        info!(logger, "Contract call reverted";
+                            "reason" => reason,
+                            "function" => call.function.signature(),
+                            "address" => format!("{:?}", call.address));
                         (None, call::Source::Rpc)
                     }
                 },
@@ -1574,7 +1610,10 @@ impl EthereumAdapterTrait for EthereumAdapter {
                     // We got a `0x` response. For old Geth, this can mean a revert. It can also be
                     // that the contract actually returned an empty response. A view call is meant
                     // to return something, so we treat empty responses the same as reverts.
-                    info!(logger, "Contract call reverted"; "reason" => "empty response");
+                    info!(logger, "Contract call reverted";
+                        "reason" => "empty response",
+                        "function" => call.function.signature(),
+                        "address" => format!("{:?}", call.address));
                     (None, call::Source::Rpc)
                 }
             }
@@ -1765,6 +1804,28 @@ pub(crate) async fn blocks_with_triggers(
         trigger_futs.push(block_futures_matching_once_filter);
     }
 
+    // Data sources with an end block handler need their end block, even if
+    // nothing else in it matches
+    let end_blocks = filter
+        .block
+        .end_blocks
+        .iter()
+        .copied()
+        .filter(|block_number| (from..=to).contains(block_number))
+        .collect_vec();
+    if !end_blocks.is_empty() {
+        let block_future = eth
+            .load_ptrs_for_blocks(logger.clone(), end_blocks)
+            .map(|ptrs| {
+                ptrs.into_iter()
+                    .map(|ptr| EthereumTrigger::Block(ptr, EthereumBlockTriggerType::DataSourceEnd))
+                    .collect()
+            })
+            .compat()
+            .boxed();
+        trigger_futs.push(block_future)
+    }
+
     // Scan for Logs
     if !filter.log.is_empty() {
         let logs_future = get_logs_and_transactions(
@@ -1781,13 +1842,31 @@ pub(crate) async fn blocks_with_triggers(
     }
     // Scan for Calls
     if !filter.call.is_empty() {
-        let calls_future = eth
+        let calls = eth
             .calls_in_block_range(&logger, subgraph_metrics.clone(), from, to, &filter.call)
-            .map(Arc::new)
-            .map(EthereumTrigger::Call)
             .collect()
-            .compat()
-            .boxed();
+            .compat();
+        // Call handlers see the trace ordinal of calls from apiVersion 0.0.10,
+        // and only if they ask for it
+        let trace_ordinals = filter.call.trace_ordinals
+            && unified_api_version.equal_or_greater_than(&API_VERSION_0_0_10);
+        let eth = eth.cheap_clone();
+        let logger = logger.cheap_clone();
+        let subgraph_metrics = subgraph_metrics.cheap_clone();
+        let calls_future = async move {
+            let mut calls = calls.await?;
+            if trace_ordinals {
+                calls = eth
+                    .add_trace_ordinals(&logger, subgraph_metrics, calls)
+                    .await?;
+            }
+            Ok(calls
+                .into_iter()
+                .map(Arc::new)
+                .map(EthereumTrigger::Call)
+                .collect())
+        }
+        .boxed();
         trigger_futs.push(calls_future)
     }
 
@@ -1797,13 +1876,10 @@ pub(crate) async fn blocks_with_triggers(
         // a `call_filter` and run `blocks_with_calls`
         let block_future = eth
             .calls_in_block_range(&logger, subgraph_metrics.clone(), from, to, &call_filter)
-            .map(|call| {
-                EthereumTrigger::Block(
-                    BlockPtr::from(&call),
-                    EthereumBlockTriggerType::WithCallTo(call.to),
-                )
-            })
             .collect()
+            .map(|calls: Vec<EthereumCall>| {
+                block_triggers_with_calls(calls.iter().map(|call| (BlockPtr::from(call), call)))
+            })
             .compat()
             .boxed();
         trigger_futs.push(block_future)
@@ -1970,6 +2046,76 @@ pub(crate) fn parse_call_triggers(
     }
 }
 
+/// Numbers the calls of each transaction in the order in which they were
+/// executed, which is the order of their trace addresses. `traces` must be
+/// all the traces of the blocks that `calls` are in. Parity's separate
+/// `suicide` traces are not counted since firehose blocks have no call for
+/// them, so that the numbers match the ones from firehose
+pub(crate) fn set_trace_ordinals<'a>(
+    calls: impl IntoIterator<Item = &'a mut EthereumCall>,
+    traces: &[Trace],
+) {
+    let mut trace_addresses: HashMap<(H256, usize), Vec<&[usize]>> = HashMap::new();
+    for trace in traces {
+        if matches!(trace.action, Action::Suicide(_)) {
+            continue;
+        }
+        if let Some(transaction_position) = trace.transaction_position {
+            trace_addresses
+                .entry((trace.block_hash, transaction_position))
+                .or_default()
+                .push(&trace.trace_address);
+        }
+    }
+    for addresses in trace_addresses.values_mut() {
+        addresses.sort();
+    }
+
+    for call in calls {
+        call.trace_ordinal = trace_addresses
+            .get(&(call.block_hash, call.transaction_index as usize))
+            .and_then(|addresses| addresses.binary_search(&call.trace_address.as_slice()).ok())
+            .map(|ordinal| ordinal as u32);
+    }
+}
+
+/// Turns calls that matched a block filter, together with the block they
+/// are in, into `WithCallTo` block triggers, one per block and called
+/// address. Each trigger lists the
+/// selectors of the functions that were called so that a data source only
+/// fires for calls to its own `functions`, even when other data sources on
+/// the same address filter for different ones.
+fn block_triggers_with_calls<'a>(
+    calls: impl IntoIterator<Item = (BlockPtr, &'a EthereumCall)>,
+) -> Vec<EthereumTrigger> {
+    let mut triggers: Vec<(BlockPtr, Address, Vec<FunctionSelector>)> = Vec::new();
+    let mut positions: HashMap<(BlockPtr, Address), usize> = HashMap::new();
+    for (block_ptr, call) in calls {
+        let pos = *positions
+            .entry((block_ptr.cheap_clone(), call.to))
+            .or_insert_with(|| {
+                triggers.push((block_ptr, call.to, Vec::new()));
+                triggers.len() - 1
+            });
+        let selectors = &mut triggers[pos].2;
+        if let Some(selector) = call.input.0.get(..4) {
+            let selector: FunctionSelector = selector.try_into().unwrap();
+            if !selectors.contains(&selector) {
+                selectors.push(selector);
+            }
+        }
+    }
+    triggers
+        .into_iter()
+        .map(|(block_ptr, address, selectors)| {
+            EthereumTrigger::Block(
+                block_ptr,
+                EthereumBlockTriggerType::WithCallTo(address, selectors),
+            )
+        })
+        .collect()
+}
+
 /// This method does not parse block triggers with `once` filters.
 /// This is because it is to be run before any other triggers are run.
 /// So we have `parse_initialization_triggers` for that.
@@ -1984,21 +2130,16 @@ pub(crate) fn parse_block_triggers(
     let block_ptr = BlockPtr::from(&block.ethereum_block);
     let trigger_every_block = block_filter.trigger_every_block;
     let call_filter = EthereumCallFilter::from(block_filter);
-    let block_ptr2 = block_ptr.cheap_clone();
     let block_ptr3 = block_ptr.cheap_clone();
     let block_number = block_ptr.number;
 
     let mut triggers = match &block.calls {
-        Some(calls) => calls
-            .iter()
-            .filter(move |call| call_filter.matches(call))
-            .map(move |call| {
-                EthereumTrigger::Block(
-                    block_ptr2.clone(),
-                    EthereumBlockTriggerType::WithCallTo(call.to),
-                )
-            })
-            .collect::<Vec<EthereumTrigger>>(),
+        Some(calls) => block_triggers_with_calls(
+            calls
+                .iter()
+                .filter(|call| call_filter.matches(call))
+                .map(|call| (block_ptr.cheap_clone(), call)),
+        ),
         None => vec![],
     };
     if trigger_every_block {
@@ -2041,11 +2182,17 @@ pub(crate) fn parse_block_triggers(
 
         if *has_polling_trigger {
             triggers.push(EthereumTrigger::Block(
-                block_ptr3,
+                block_ptr3.clone(),
                 EthereumBlockTriggerType::End,
             ));
         }
     }
+    if block_filter.end_blocks.contains(&block_number) {
+        triggers.push(EthereumTrigger::Block(
+            block_ptr3,
+            EthereumBlockTriggerType::DataSourceEnd,
+        ));
+    }
     triggers
 }
 
@@ -2468,16 +2615,27 @@ async fn get_logs_and_transactions(
 
     // Not all logs have associated transaction hashes, nor do all triggers require them.
     // We also restrict receipts retrieval for some api versions.
+    let requires_receipt = |log: &Log| {
+        unified_api_version.equal_or_greater_than(&API_VERSION_0_0_7)
+            && log.topics.first().map_or(false, |signature| {
+                log_filter.requires_transaction_receipt(signature, Some(&log.address), &log.topics)
+            })
+    };
+    // From apiVersion 0.0.10, mappings see the index of a log within its
+    // transaction. Providers that don't return it with the log need the
+    // receipt to work it out
+    let requires_transaction_log_index = |log: &Log| {
+        unified_api_version.equal_or_greater_than(&API_VERSION_0_0_10)
+            && log.transaction_log_index.is_none()
+    };
+    let receipt_transactions: HashSet<H256> = logs
+        .iter()
+        .filter(|log| requires_receipt(log))
+        .filter_map(|log| log.transaction_hash)
+        .collect();
     let transaction_hashes_by_block: HashMap<H256, HashSet<H256>> = logs
         .iter()
-        .filter(|_| unified_api_version.equal_or_greater_than(&API_VERSION_0_0_7))
-        .filter(|log| {
-            if let Some(signature) = log.topics.first() {
-                log_filter.requires_transaction_receipt(signature, Some(&log.address), &log.topics)
-            } else {
-                false
-            }
-        })
+        .filter(|log| requires_receipt(log) || requires_transaction_log_index(log))
         .filter_map(|log| {
             if let (Some(block), Some(txn)) = (log.block_hash, log.transaction_hash) {
                 Some((block, txn))
@@ -2506,9 +2664,16 @@ async fn get_logs_and_transactions(
 
     // Associate each log with its receipt, when possible
     let mut log_triggers = Vec::new();
-    for log in logs.into_iter() {
+    for mut log in logs.into_iter() {
+        if requires_transaction_log_index(&log) {
+            let receipt = log
+                .transaction_hash
+                .and_then(|txn| transaction_receipts_by_hash.get(&txn));
+            log.transaction_log_index = transaction_log_index(&log, receipt.map(Arc::as_ref));
+        }
         let optional_receipt = log
             .transaction_hash
+            .filter(|txn| receipt_transactions.contains(txn))
             .and_then(|txn| transaction_receipts_by_hash.get(&txn).cloned());
         let value = EthereumTrigger::Log(LogRef::FullLog(Arc::new(log), optional_receipt));
         log_triggers.push(value);
@@ -2597,24 +2762,60 @@ async fn get_transaction_receipts_for_transaction_hashes(
     Ok(receipts_by_hash)
 }
 
+/// Decode the data returned by a reverted call into a human readable
+/// reason. Solidity's `Error(string)` and `Panic(uint256)` are decoded;
+/// for custom errors, which we can't decode without the contract's ABI,
+/// the selector and the raw arguments are returned. Reverts can carry
+/// arbitrary amounts of data, so messages and arguments longer than
+/// `GRAPH_DECODE_ERROR_DATA_LIMIT` are truncated.
+fn decode_revert_reason(data: &[u8]) -> Option<String> {
+    if data.len() < 4 {
+        return None;
+    }
+    let limit = graph::env::ENV_VARS.mappings.decode_error_data_limit;
+    let (selector, args) = data.split_at(4);
+    if selector == &tiny_keccak::keccak256(b"Error(string)")[..4] {
+        ethabi::decode(&[ParamType::String], args)
+            .ok()
+            .and_then(|tokens| tokens[0].clone().into_string())
+            .map(|message| match message.char_indices().nth(limit) {
+                Some((end, _)) => format!("{}...", &message[..end]),
+                None => message,
+            })
+    } else if selector == &tiny_keccak::keccak256(b"Panic(uint256)")[..4] {
+        ethabi::decode(&[ParamType::Uint(256)], args)
+            .ok()
+            .and_then(|tokens| tokens[0].clone().into_uint())
+            .map(|code| format!("Panic(0x{:x})", code))
+    } else {
+        Some(format!(
+            "custom error 0x{} with data {}",
+            hex::encode(selector),
+            truncated_hex(args, limit)
+        ))
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
     use crate::trigger::{EthereumBlockTriggerType, EthereumTrigger};
 
     use super::{
-        check_block_receipt_support, parse_block_triggers, EthereumBlock, EthereumBlockFilter,
-        EthereumBlockWithCalls,
+        check_block_receipt_support, decode_revert_reason, parse_block_triggers, EthereumBlock,
+        EthereumBlockFilter, EthereumBlockWithCalls,
     };
     use graph::blockchain::BlockPtr;
     use graph::prelude::ethabi::ethereum_types::U64;
+    use graph::prelude::ethabi::{self, Token};
     use graph::prelude::tokio::{self};
     use graph::prelude::web3::transports::test::TestTransport;
     use graph::prelude::web3::types::{Address, Block, Bytes, H256};
     use graph::prelude::web3::Web3;
     use graph::prelude::EthereumCall;
+    use graph::prelude::{hex, tiny_keccak};
     use jsonrpc_core::serde_json::{self, Value};
-    use std::collections::HashSet;
+    use std::collections::{HashMap, HashSet};
     use std::iter::FromIterator;
     use std::sync::Arc;
 
@@ -2648,6 +2849,8 @@ mod tests {
                 &EthereumBlockFilter {
                     polling_intervals: HashSet::new(),
                     contract_addresses: HashSet::from_iter(vec![(10, address(1))]),
+                    function_selectors: HashMap::new(),
+                    end_blocks: HashSet::new(),
                     trigger_every_block: true,
                 },
                 &block
@@ -2656,6 +2859,44 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_block_triggers_data_source_end() {
+        let block = EthereumBlockWithCalls {
+            ethereum_block: EthereumBlock {
+                block: Arc::new(Block {
+                    hash: Some(hash(2)),
+                    number: Some(U64::from(2)),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            calls: Some(vec![]),
+        };
+
+        let filter = |end_block| EthereumBlockFilter {
+            polling_intervals: HashSet::new(),
+            contract_addresses: HashSet::new(),
+            function_selectors: HashMap::new(),
+            end_blocks: HashSet::from_iter(vec![end_block]),
+            trigger_every_block: false,
+        };
+
+        assert_eq!(
+            vec![EthereumTrigger::Block(
+                BlockPtr::from((hash(2), 2)),
+                EthereumBlockTriggerType::DataSourceEnd
+            )],
+            parse_block_triggers(&filter(2), &block),
+            "the end block should generate a trigger"
+        );
+
+        assert_eq!(
+            Vec::<EthereumTrigger>::new(),
+            parse_block_triggers(&filter(3), &block),
+            "other blocks should not generate a trigger"
+        );
+    }
+
     #[tokio::test]
     async fn test_check_block_receipts_support() {
         let mut transport = TestTransport::default();
@@ -2808,6 +3049,8 @@ mod tests {
                 &EthereumBlockFilter {
                     polling_intervals: HashSet::new(),
                     contract_addresses: HashSet::from_iter(vec![(1, address(1))]),
+                    function_selectors: HashMap::new(),
+                    end_blocks: HashSet::new(),
                     trigger_every_block: false,
                 },
                 &block
@@ -2837,12 +3080,14 @@ mod tests {
         assert_eq!(
             vec![EthereumTrigger::Block(
                 BlockPtr::from((hash(2), 2)),
-                EthereumBlockTriggerType::WithCallTo(address(4))
+                EthereumBlockTriggerType::WithCallTo(address(4), vec![[1u8; 4]])
             )],
             parse_block_triggers(
                 &EthereumBlockFilter {
                     polling_intervals: HashSet::new(),
                     contract_addresses: HashSet::from_iter(vec![(1, address(4))]),
+                    function_selectors: HashMap::new(),
+                    end_blocks: HashSet::new(),
                     trigger_every_block: false,
                 },
                 &block
@@ -2851,6 +3096,142 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_block_triggers_specific_function_call() {
+        let block_with_call = |input: Vec<u8>| EthereumBlockWithCalls {
+            ethereum_block: EthereumBlock {
+                block: Arc::new(Block {
+                    hash: Some(hash(2)),
+                    number: Some(U64::from(2)),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            calls: Some(vec![EthereumCall {
+                to: address(4),
+                input: bytes(input),
+                ..Default::default()
+            }]),
+        };
+        let filter = EthereumBlockFilter {
+            polling_intervals: HashSet::new(),
+            contract_addresses: HashSet::from_iter(vec![(1, address(4))]),
+            function_selectors: HashMap::from_iter(vec![(
+                address(4),
+                HashSet::from_iter(vec![[1u8; 4]]),
+            )]),
+            end_blocks: HashSet::new(),
+            trigger_every_block: false,
+        };
+
+        let mut unrelated_call = vec![2; 4];
+        unrelated_call.extend(vec![1; 32]);
+        assert_eq!(
+            Vec::<EthereumTrigger>::new(),
+            parse_block_triggers(&filter, &block_with_call(unrelated_call)),
+            "block filter names a function of address 4 but the block only calls another one"
+        );
+
+        assert_eq!(
+            vec![EthereumTrigger::Block(
+                BlockPtr::from((hash(2), 2)),
+                EthereumBlockTriggerType::WithCallTo(address(4), vec![[1u8; 4]])
+            )],
+            parse_block_triggers(&filter, &block_with_call(vec![1; 36])),
+            "block filter names a function of address 4 and the block calls it"
+        );
+    }
+
+    #[test]
+    fn parse_block_triggers_one_trigger_per_called_address() {
+        let call = |to: u64, selector: u8| EthereumCall {
+            to: address(to),
+            input: bytes(vec![selector; 36]),
+            ..Default::default()
+        };
+        let block = EthereumBlockWithCalls {
+            ethereum_block: EthereumBlock {
+                block: Arc::new(Block {
+                    hash: Some(hash(2)),
+                    number: Some(U64::from(2)),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            calls: Some(vec![call(4, 1), call(5, 3), call(4, 2), call(4, 1)]),
+        };
+        let filter = EthereumBlockFilter {
+            polling_intervals: HashSet::new(),
+            contract_addresses: HashSet::from_iter(vec![(1, address(4)), (1, address(5))]),
+            function_selectors: HashMap::new(),
+            end_blocks: HashSet::new(),
+            trigger_every_block: false,
+        };
+
+        assert_eq!(
+            vec![
+                EthereumTrigger::Block(
+                    BlockPtr::from((hash(2), 2)),
+                    EthereumBlockTriggerType::WithCallTo(address(4), vec![[1u8; 4], [2u8; 4]])
+                ),
+                EthereumTrigger::Block(
+                    BlockPtr::from((hash(2), 2)),
+                    EthereumBlockTriggerType::WithCallTo(address(5), vec![[3u8; 4]])
+                ),
+            ],
+            parse_block_triggers(&filter, &block),
+            "calls to the same address are merged into one trigger listing the called functions"
+        );
+    }
+
+    #[test]
+    fn decode_revert_reasons() {
+        let with_selector = |signature: &[u8], args: &[Token]| {
+            let mut data = tiny_keccak::keccak256(signature)[..4].to_vec();
+            data.extend(ethabi::encode(args));
+            data
+        };
+
+        let error = with_selector(
+            b"Error(string)",
+            &[Token::String("insufficient".to_string())],
+        );
+        assert_eq!(
+            Some("insufficient".to_string()),
+            decode_revert_reason(&error)
+        );
+
+        let panic = with_selector(b"Panic(uint256)", &[Token::Uint(0x11.into())]);
+        assert_eq!(
+            Some("Panic(0x11)".to_string()),
+            decode_revert_reason(&panic)
+        );
+
+        let custom = with_selector(b"Unauthorized(address)", &[Token::Address(address(1))]);
+        assert_eq!(
+            Some(format!(
+                "custom error 0x{} with data 0x{}",
+                hex::encode(&custom[..4]),
+                hex::encode(&custom[4..])
+            )),
+            decode_revert_reason(&custom)
+        );
+
+        assert_eq!(None, decode_revert_reason(&[0x08, 0xc3]));
+
+        // Long messages and custom error arguments are truncated
+        let limit = graph::env::ENV_VARS.mappings.decode_error_data_limit;
+        let long_error = with_selector(b"Error(string)", &[Token::String("x".repeat(limit + 10))]);
+        assert_eq!(
+            Some(format!("{}...", "x".repeat(limit))),
+            decode_revert_reason(&long_error)
+        );
+
+        let long_custom = with_selector(b"Data(bytes)", &[Token::Bytes(vec![1; limit])]);
+        let reason = decode_revert_reason(&long_custom).unwrap();
+        assert!(reason.ends_with(&format!("({} of {} bytes)", limit, long_custom.len() - 4)));
+    }
+
     fn address(id: u64) -> Address {
         Address::from_low_u64_be(id)
     }
@@ -16,7 +16,7 @@ use graph::{
 use graph_runtime_derive::AscType;
 use graph_runtime_wasm::asc_abi::class::{
     Array, AscAddress, AscBigInt, AscEnum, AscH160, AscString, AscWrapped, EthereumValueKind,
-    Uint8Array,
+    TypedArray, Uint8Array,
 };
 use semver::Version;
 
@@ -413,6 +413,33 @@ where
     const INDEX_ASC_TYPE_ID: IndexForAscTypeId = IndexForAscTypeId::EthereumCall;
 }
 
+/// Introduced in API Version 0.0.10, this is the same as [`AscEthereumCall_0_0_3`] with added
+/// `trace_address` and `trace_ordinal` fields. `trace_ordinal` is null if it is not known.
+#[repr(C)]
+#[derive(AscType)]
+pub(crate) struct AscEthereumCall_0_0_10<T, B>
+where
+    T: AscType,
+    B: AscType,
+{
+    pub to: AscPtr<AscAddress>,
+    pub from: AscPtr<AscAddress>,
+    pub block: AscPtr<B>,
+    pub transaction: AscPtr<T>,
+    pub inputs: AscPtr<AscLogParamArray>,
+    pub outputs: AscPtr<AscLogParamArray>,
+    pub trace_address: AscPtr<TypedArray<i32>>,
+    pub trace_ordinal: AscPtr<AscBigInt>,
+}
+
+impl<T, B> AscIndexId for AscEthereumCall_0_0_10<T, B>
+where
+    T: AscType,
+    B: AscType,
+{
+    const INDEX_ASC_TYPE_ID: IndexForAscTypeId = IndexForAscTypeId::EthereumCall;
+}
+
 impl ToAscObj<AscEthereumBlock> for EthereumBlockData {
     fn to_asc_obj<H: AscHeap + ?Sized>(
         &self,
@@ -771,6 +798,36 @@ impl ToAscObj<AscEthereumCall_0_0_3<AscEthereumTransaction_0_0_6, AscEthereumBlo
     }
 }
 
+impl ToAscObj<AscEthereumCall_0_0_10<AscEthereumTransaction_0_0_6, AscEthereumBlock_0_0_6>>
+    for EthereumCallData
+{
+    fn to_asc_obj<H: AscHeap + ?Sized>(
+        &self,
+        heap: &mut H,
+        gas: &GasCounter,
+    ) -> Result<
+        AscEthereumCall_0_0_10<AscEthereumTransaction_0_0_6, AscEthereumBlock_0_0_6>,
+        HostExportError,
+    > {
+        let call: AscEthereumCall_0_0_3<AscEthereumTransaction_0_0_6, AscEthereumBlock_0_0_6> =
+            self.to_asc_obj(heap, gas)?;
+        let trace_address: Vec<i32> = self.trace_address.iter().map(|i| *i as i32).collect();
+        Ok(AscEthereumCall_0_0_10 {
+            to: call.to,
+            from: call.from,
+            block: call.block,
+            transaction: call.transaction,
+            inputs: call.inputs,
+            outputs: call.outputs,
+            trace_address: asc_new(heap, trace_address.as_slice(), gas)?,
+            trace_ordinal: self
+                .trace_ordinal
+                .map(|ordinal| asc_new(heap, &BigInt::from(ordinal as u64), gas))
+                .unwrap_or(Ok(AscPtr::null()))?,
+        })
+    }
+}
+
 impl ToAscObj<AscLogParam> for ethabi::LogParam {
     fn to_asc_obj<H: AscHeap + ?Sized>(
         &self,
@@ -340,13 +340,13 @@ fn eth_call(
 
     let elapsed = start_time.elapsed();
 
-    if source.observe() {
-        metrics.observe_eth_call_execution_time(
-            elapsed.as_secs_f64(),
-            &unresolved_call.contract_name,
-            &unresolved_call.function_name,
-        );
-    }
+    metrics.observe_eth_call(
+        &unresolved_call.contract_name,
+        &unresolved_call.function_name,
+        &source,
+        elapsed.as_secs_f64(),
+        matches!(result, Ok(None)),
+    );
 
     debug!(logger, "Contract call finished";
               "address" => format!("0x{:x}", &unresolved_call.contract_address),
@@ -357,7 +357,7 @@ fn eth_call(
               "result" => result_as_string(&result),
               "block_hash" => block_ptr.hash_hex(),
               "block_number" => block_ptr.block_number(),
-              "source" => source.to_string());
+              "source" => source.as_str());
 
     result
 }
@@ -1,17 +1,56 @@
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use graph::{
-    blockchain::{block_stream::BlockWithTriggers, BlockPtr},
+    blockchain::{
+        self, block_stream::BlockWithTriggers, Block, BlockPtr, BlockTime, DeclaredFileSource,
+        MappingTriggerTrait, TriggerWithHandler,
+    },
+    components::{
+        metrics::gas::GasMetrics,
+        store::SubgraphFork,
+        subgraph::{DeterministicDecodeError, MappingError, SharedProofOfIndexing},
+        trigger_processor::{HostedTrigger, RunnableTriggers},
+    },
+    data::{
+        store::ethereum::call,
+        subgraph::{API_VERSION_0_0_10, API_VERSION_0_0_9},
+    },
+    data_source,
     prelude::{
-        web3::types::{Address, Bytes, Log, H160, H256, U64},
-        EthereumCall, LightEthereumBlock,
+        async_trait,
+        ethabi::{self, Contract, Token},
+        serde_json as json,
+        web3::types::{
+            Address, Bytes, Log, Trace, Transaction, TransactionReceipt, H160, H256, U64,
+        },
+        BlockNumber, BlockState, CachedEthereumCall, CheapClone, DeploymentHash, Error,
+        EthereumBlock, EthereumBlockWithCalls, EthereumCall, EthereumCallCache, HostMetrics,
+        LightEthereumBlock, MetricsRegistry, RuntimeHost, StopwatchMetrics,
+        SubgraphInstanceMetrics,
     },
+    runtime::{AscPtr, AscType},
     slog::{self, o, Logger},
 };
 
 use crate::{
+    adapter::EthereumBlockFilter,
     chain::BlockFinality,
-    trigger::{EthereumBlockTriggerType, EthereumTrigger, LogRef},
+    codec,
+    data_source::{
+        duplicate_log_triggers, truncated_hex, BlockHandlerFilter, CallDecls, DataSource,
+        DecoderHook, EventHandlers, FileSourceDecl, FileSourceTransform, Mapping, MappingABI,
+        MappingBlockHandler, MappingCallHandler, MappingEventHandler,
+    },
+    ethereum_adapter::{parse_block_triggers, set_trace_ordinals},
+    network::EthereumNetworkAdapters,
+    runtime::abi::{
+        AscEthereumBlock_0_0_6, AscEthereumCall_0_0_10, AscEthereumCall_0_0_3,
+        AscEthereumTransaction_0_0_6,
+    },
+    trigger::{
+        transaction_log_index, EthereumBlockTriggerType, EthereumTrigger, LogRef, MappingTrigger,
+    },
+    Chain,
 };
 
 #[test]
@@ -23,7 +62,7 @@ fn test_trigger_ordering() {
 
     let block2 = EthereumTrigger::Block(
         BlockPtr::from((H256::random(), 0u64)),
-        EthereumBlockTriggerType::WithCallTo(Address::random()),
+        EthereumBlockTriggerType::WithCallTo(Address::random(), vec![]),
     );
 
     let mut call1 = EthereumCall::default();
@@ -122,7 +161,7 @@ fn test_trigger_dedup() {
 
     let block2 = EthereumTrigger::Block(
         BlockPtr::from((H256::random(), 0u64)),
-        EthereumBlockTriggerType::WithCallTo(Address::random()),
+        EthereumBlockTriggerType::WithCallTo(Address::random(), vec![]),
     );
 
     // duplicate block2
@@ -208,3 +247,1102 @@ fn test_trigger_dedup() {
         vec![log1, log2, call1, log3, call2, call3, block2, block1]
     );
 }
+
+#[test]
+fn test_trigger_ordering_within_transaction() {
+    let ptr = BlockPtr::from((H256::zero(), 0u64));
+    let start = EthereumTrigger::Block(ptr.clone(), EthereumBlockTriggerType::Start);
+    let with_call_to = EthereumTrigger::Block(
+        ptr.clone(),
+        EthereumBlockTriggerType::WithCallTo(Address::random(), vec![]),
+    );
+    let end = EthereumTrigger::Block(ptr.clone(), EthereumBlockTriggerType::End);
+    let data_source_end = EthereumTrigger::Block(ptr, EthereumBlockTriggerType::DataSourceEnd);
+
+    fn call(tx_index: u64, trace_address: Vec<usize>) -> EthereumTrigger {
+        let mut call = EthereumCall::default();
+        call.transaction_index = tx_index;
+        call.trace_address = trace_address;
+        EthereumTrigger::Call(Arc::new(call))
+    }
+
+    fn log(tx_index: u64, log_index: u64) -> EthereumTrigger {
+        EthereumTrigger::Log(LogRef::FullLog(
+            Arc::new(Log {
+                address: H160::default(),
+                topics: vec![],
+                data: Bytes::default(),
+                block_hash: Some(H256::zero()),
+                block_number: Some(U64::zero()),
+                transaction_hash: Some(H256::from_low_u64_be(tx_index)),
+                transaction_index: Some(tx_index.into()),
+                log_index: Some(log_index.into()),
+                transaction_log_index: Some(log_index.into()),
+                log_type: Some("".into()),
+                removed: Some(false),
+            }),
+            None,
+        ))
+    }
+
+    // Transaction 1 has events and a tree of calls, transaction 2 has a
+    // call and an event
+    let expected = vec![
+        start.clone(),
+        log(1, 0),
+        log(1, 1),
+        call(1, vec![]),
+        call(1, vec![0]),
+        call(1, vec![0, 0]),
+        call(1, vec![0, 1]),
+        call(1, vec![1]),
+        log(2, 2),
+        call(2, vec![]),
+        with_call_to.clone(),
+        end.clone(),
+        data_source_end.clone(),
+    ];
+
+    let triggers = vec![
+        data_source_end,
+        with_call_to,
+        call(2, vec![]),
+        call(1, vec![1]),
+        log(2, 2),
+        end,
+        call(1, vec![0, 1]),
+        call(1, vec![0]),
+        log(1, 1),
+        call(1, vec![0, 0]),
+        start,
+        call(1, vec![]),
+        log(1, 0),
+    ];
+
+    let logger = Logger::root(slog::Discard, o!());
+
+    let mut b: LightEthereumBlock = Default::default();
+    b.number = Some(Default::default());
+    b.hash = Some(Default::default());
+
+    let block_with_triggers = BlockWithTriggers::<crate::Chain>::new(
+        BlockFinality::Final(Arc::new(b)),
+        triggers,
+        &logger,
+    );
+
+    assert_eq!(block_with_triggers.trigger_data, expected);
+}
+
+const TRANSFER_ABI: &str = r#"[
+    {
+        "type": "function",
+        "name": "transfer",
+        "inputs": [
+            { "name": "to", "type": "address" },
+            { "name": "value", "type": "uint256" }
+        ],
+        "outputs": [],
+        "stateMutability": "nonpayable"
+    },
+    {
+        "type": "function",
+        "name": "transfer",
+        "inputs": [
+            { "name": "to", "type": "address" },
+            { "name": "value", "type": "uint256" },
+            { "name": "data", "type": "bytes" }
+        ],
+        "outputs": [],
+        "stateMutability": "nonpayable"
+    },
+    {
+        "type": "event",
+        "name": "Transfer",
+        "inputs": [
+            { "name": "from", "type": "address", "indexed": true },
+            { "name": "to", "type": "address", "indexed": true },
+            { "name": "value", "type": "uint256", "indexed": false }
+        ],
+        "anonymous": false
+    },
+    {
+        "type": "event",
+        "name": "MetadataSet",
+        "inputs": [
+            { "name": "id", "type": "uint256", "indexed": true },
+            { "name": "digest", "type": "bytes32", "indexed": false },
+            { "name": "uri", "type": "string", "indexed": false }
+        ],
+        "anonymous": false
+    }
+]"#;
+
+fn transfer_data_source(api_version: semver::Version) -> DataSource {
+    let contract = Contract::load(TRANSFER_ABI.as_bytes()).unwrap();
+    let contract_abi = Arc::new(MappingABI::new("Token".to_string(), contract));
+    let call_handlers = vec![
+        MappingCallHandler::new(
+            "transfer(address,uint256)".to_string(),
+            "handleTransfer".to_string(),
+        ),
+        MappingCallHandler::new(
+            "transfer(address,uint256,bytes)".to_string(),
+            "handleTransferWithData".to_string(),
+        ),
+    ];
+
+    DataSource {
+        kind: "ethereum/contract".to_string(),
+        network: Some("mainnet".to_string()),
+        name: "Token".to_string(),
+        manifest_idx: 0,
+        address: Some(Address::from_low_u64_be(1)),
+        start_block: 0,
+        end_block: None,
+        mapping: Mapping {
+            kind: "ethereum/events".to_string(),
+            api_version,
+            language: "wasm/assemblyscript".to_string(),
+            entities: vec![],
+            abis: vec![contract_abi.cheap_clone()],
+            block_handlers: vec![],
+            call_handlers,
+            event_handlers: EventHandlers::default(),
+            end_block_handler: None,
+            runtime: Arc::new(vec![]),
+            link: "link".into(),
+        },
+        context: Arc::new(None),
+        creation_block: None,
+        contract_abi,
+    }
+}
+
+fn transfer_call(data_source: &DataSource, signature: &str, input: Vec<u8>) -> EthereumCall {
+    let function = data_source
+        .contract_abi
+        .contract
+        .functions_by_name("transfer")
+        .unwrap()
+        .iter()
+        .find(|f| f.signature() == signature)
+        .unwrap();
+
+    let mut calldata = function.short_signature().to_vec();
+    calldata.extend(input);
+
+    let mut call = EthereumCall::default();
+    call.to = data_source.address.unwrap();
+    call.transaction_hash = Some(H256::from_low_u64_be(7));
+    call.input = Bytes(calldata);
+    call
+}
+
+fn match_call(
+    data_source: &DataSource,
+    call: EthereumCall,
+    logger: &Logger,
+) -> Result<Option<TriggerWithHandler<crate::Chain>>, anyhow::Error> {
+    let mut block = LightEthereumBlock::default();
+    block.number = Some(U64::from(1));
+    block.hash = Some(H256::from_low_u64_be(1));
+    block.transactions.push(Transaction {
+        hash: call.transaction_hash.unwrap(),
+        ..Transaction::default()
+    });
+    let block = Arc::new(BlockFinality::Final(Arc::new(block)));
+
+    blockchain::DataSource::match_and_decode(
+        data_source,
+        &EthereumTrigger::Call(Arc::new(call)),
+        &block,
+        logger,
+    )
+}
+
+#[test]
+fn call_handlers_match_overloads_by_selector() {
+    let logger = Logger::root(slog::Discard, o!());
+    let data_source = transfer_data_source(API_VERSION_0_0_9);
+    let to = Token::Address(Address::from_low_u64_be(2));
+    let value = Token::Uint(100.into());
+
+    let args = ethabi::encode(&[to.clone(), value.clone()]);
+    let call = transfer_call(&data_source, "transfer(address,uint256)", args);
+    let trigger = match_call(&data_source, call, &logger).unwrap().unwrap();
+    assert_eq!("handleTransfer", trigger.handler_name());
+
+    let args = ethabi::encode(&[to, value, Token::Bytes(vec![1, 2, 3])]);
+    let call = transfer_call(&data_source, "transfer(address,uint256,bytes)", args);
+    let trigger = match_call(&data_source, call, &logger).unwrap().unwrap();
+    assert_eq!("handleTransferWithData", trigger.handler_name());
+}
+
+#[test]
+fn call_handlers_see_the_trace_ordinal_if_they_ask_for_it() {
+    let logger = Logger::root(slog::Discard, o!());
+    let mut data_source = transfer_data_source(API_VERSION_0_0_10);
+    let args = ethabi::encode(&[
+        Token::Address(Address::from_low_u64_be(2)),
+        Token::Uint(100.into()),
+    ]);
+    let mut call = transfer_call(&data_source, "transfer(address,uint256)", args);
+    call.trace_ordinal = Some(3);
+
+    let trace_ordinal = |data_source: &DataSource| {
+        let trigger = match_call(data_source, call.clone(), &logger)
+            .unwrap()
+            .unwrap();
+        match trigger.trigger {
+            MappingTrigger::Call { trace_ordinal, .. } => trace_ordinal,
+            trigger => panic!("expected a call trigger, got {:?}", trigger),
+        }
+    };
+
+    assert_eq!(None, trace_ordinal(&data_source));
+
+    data_source.mapping.call_handlers[0].trace_ordinal = true;
+    assert_eq!(Some(3), trace_ordinal(&data_source));
+}
+
+#[test]
+fn call_with_matching_selector_and_malformed_input() {
+    let logger = Logger::root(slog::Discard, o!());
+
+    // A proxy forwarding a call whose selector is `transfer(address,uint256)`
+    // but whose calldata is too short to hold the arguments
+    let malformed = |data_source: &DataSource| {
+        let call = transfer_call(data_source, "transfer(address,uint256)", vec![0u8; 3]);
+        match_call(data_source, call, &logger)
+    };
+
+    // Older mappings skip the call
+    let data_source = transfer_data_source(API_VERSION_0_0_9);
+    assert!(malformed(&data_source).unwrap().is_none());
+
+    // Newer mappings fail deterministically
+    let data_source = transfer_data_source(API_VERSION_0_0_10);
+    let err = malformed(&data_source).unwrap_err();
+    let err = err.downcast::<DeterministicDecodeError>().unwrap();
+    assert_eq!("handleTransfer", err.handler);
+    let err = err.to_string();
+    assert!(err.contains("Generating function inputs for the call to `transfer`"));
+    assert!(err.contains("expected inputs (address to, uint256 value)"));
+    assert!(err.contains("raw input: 0xa9059cbb000000"));
+}
+
+#[test]
+fn truncated_hex_bounds_undecodable_data() {
+    let data = vec![0xab; 600];
+    assert_eq!("0xabab", truncated_hex(&data[..2], 2));
+    assert_eq!(
+        format!("0x{}... (512 of 600 bytes)", "ab".repeat(512)),
+        truncated_hex(&data, 512)
+    );
+}
+
+#[test]
+fn end_block_handler_matches_only_at_end_block() {
+    let logger = Logger::root(slog::Discard, o!());
+    let mut data_source = transfer_data_source(API_VERSION_0_0_9);
+    data_source.end_block = Some(5);
+    data_source.mapping.end_block_handler = Some("handleEnd".to_string());
+
+    let match_block = |number: u64| {
+        let mut block = LightEthereumBlock::default();
+        block.number = Some(U64::from(number));
+        block.hash = Some(H256::from_low_u64_be(number));
+        let ptr = BlockPtr::from((H256::from_low_u64_be(number), number));
+        let block = Arc::new(BlockFinality::Final(Arc::new(block)));
+
+        blockchain::DataSource::match_and_decode(
+            &data_source,
+            &EthereumTrigger::Block(ptr, EthereumBlockTriggerType::DataSourceEnd),
+            &block,
+            &logger,
+        )
+        .unwrap()
+    };
+
+    assert!(match_block(4).is_none());
+    assert_eq!("handleEnd", match_block(5).unwrap().handler_name());
+}
+
+#[test]
+fn call_filtered_block_handlers_on_one_address_fire_for_their_own_functions() {
+    let logger = Logger::root(slog::Discard, o!());
+    let call_filtered = |name: &str, function: &str| {
+        let mut data_source = transfer_data_source(API_VERSION_0_0_9);
+        data_source.name = name.to_string();
+        data_source.mapping.call_handlers = vec![];
+        data_source.mapping.block_handlers = vec![MappingBlockHandler {
+            handler: format!("handle{}", name),
+            filter: Some(BlockHandlerFilter::call(vec![function.to_string()])),
+        }];
+        data_source
+    };
+    let a = call_filtered("A", "transfer(address,uint256)");
+    let b = call_filtered("B", "transfer(address,uint256,bytes)");
+    let filter = EthereumBlockFilter::from_data_sources([&a, &b]);
+
+    // The block only calls the function `b` is interested in
+    let args = ethabi::encode(&[
+        Token::Address(Address::from_low_u64_be(2)),
+        Token::Uint(100.into()),
+        Token::Bytes(vec![1, 2, 3]),
+    ]);
+    let call = transfer_call(&b, "transfer(address,uint256,bytes)", args);
+    let mut block = LightEthereumBlock::default();
+    block.number = Some(U64::from(1));
+    block.hash = Some(H256::from_low_u64_be(1));
+    let block_with_calls = EthereumBlockWithCalls {
+        ethereum_block: EthereumBlock {
+            block: Arc::new(block.clone()),
+            ..Default::default()
+        },
+        calls: Some(vec![call]),
+    };
+    let triggers = parse_block_triggers(&filter, &block_with_calls);
+    assert_eq!(1, triggers.len());
+
+    let block = Arc::new(BlockFinality::Final(Arc::new(block)));
+    let handler = |data_source: &DataSource| {
+        blockchain::DataSource::match_and_decode(data_source, &triggers[0], &block, &logger)
+            .unwrap()
+            .map(|trigger| trigger.handler_name().to_string())
+    };
+    assert_eq!(None, handler(&a));
+    assert_eq!(Some("handleB".to_string()), handler(&b));
+}
+
+#[test]
+fn wildcard_event_handlers_skip_logs_with_incompatible_layouts() {
+    let logger = Logger::root(slog::Discard, o!());
+    let mut data_source = transfer_data_source(API_VERSION_0_0_9);
+    data_source.address = None;
+    data_source.mapping.event_handlers = EventHandlers::new(vec![MappingEventHandler {
+        event: "Transfer(indexed address,indexed address,uint256)".to_string(),
+        topic0: None,
+        topic1: None,
+        topic2: None,
+        topic3: None,
+        handler: "handleTransfer".to_string(),
+        receipt: false,
+        calls: CallDecls::default(),
+        file_sources: vec![],
+        dedupe: false,
+    }]);
+
+    let tx_hash = H256::from_low_u64_be(7);
+    let mut block = LightEthereumBlock::default();
+    block.number = Some(U64::from(1));
+    block.hash = Some(H256::from_low_u64_be(1));
+    block.transactions.push(Transaction {
+        hash: tx_hash,
+        ..Transaction::default()
+    });
+    let block = Arc::new(BlockFinality::Final(Arc::new(block)));
+
+    let match_log = |address: u64, topics: Vec<H256>, data: Vec<u8>| {
+        let log = Log {
+            address: Address::from_low_u64_be(address),
+            topics,
+            data: Bytes(data),
+            block_hash: Some(H256::from_low_u64_be(1)),
+            block_number: Some(U64::from(1)),
+            transaction_hash: Some(tx_hash),
+            transaction_index: Some(U64::zero()),
+            log_index: Some(0.into()),
+            transaction_log_index: Some(0.into()),
+            log_type: None,
+            removed: Some(false),
+        };
+        blockchain::DataSource::match_and_decode(
+            &data_source,
+            &EthereumTrigger::Log(LogRef::FullLog(Arc::new(log), None)),
+            &block,
+            &logger,
+        )
+    };
+
+    let topic0 = data_source.mapping.event_handlers[0].topic0();
+    let from = H256::from(Address::from_low_u64_be(2));
+    let to = H256::from(Address::from_low_u64_be(3));
+
+    // ERC-20 `Transfer` from any contract is handled
+    let value = ethabi::encode(&[Token::Uint(100.into())]);
+    let trigger = match_log(10, vec![topic0, from, to], value).unwrap();
+    assert_eq!("handleTransfer", trigger.unwrap().handler_name());
+
+    // ERC-721 `Transfer` shares topic0 but indexes the token id, so it is
+    // skipped instead of failing the subgraph
+    let token_id = H256::from_low_u64_be(100);
+    let trigger = match_log(11, vec![topic0, from, to, token_id], vec![]).unwrap();
+    assert!(trigger.is_none());
+}
+
+#[test]
+fn event_handlers_sharing_topic0_match_in_manifest_order() {
+    let logger = Logger::root(slog::Discard, o!());
+    let handler = |handler: &str, event: &str, topic1: Option<Vec<H256>>| MappingEventHandler {
+        event: event.to_string(),
+        topic0: None,
+        topic1,
+        topic2: None,
+        topic3: None,
+        handler: handler.to_string(),
+        receipt: false,
+        calls: CallDecls::default(),
+        file_sources: vec![],
+        dedupe: false,
+    };
+    let transfer = "Transfer(indexed address,indexed address,uint256)";
+    let from = H256::from(Address::from_low_u64_be(2));
+    let to = H256::from(Address::from_low_u64_be(3));
+
+    let mut data_source = transfer_data_source(API_VERSION_0_0_9);
+    data_source.mapping.event_handlers = EventHandlers::new(vec![
+        handler("handleTransfer", transfer, None),
+        handler(
+            "handleMetadataSet",
+            "MetadataSet(indexed uint256,bytes32,string)",
+            None,
+        ),
+        handler("handleTransferFrom", transfer, Some(vec![from])),
+    ]);
+    let topic0 = data_source.mapping.event_handlers[0].topic0();
+
+    let tx_hash = H256::from_low_u64_be(7);
+    let mut block = LightEthereumBlock::default();
+    block.number = Some(U64::from(1));
+    block.hash = Some(H256::from_low_u64_be(1));
+    block.transactions.push(Transaction {
+        hash: tx_hash,
+        ..Transaction::default()
+    });
+    let block = Arc::new(BlockFinality::Final(Arc::new(block)));
+
+    let log = |topics: Vec<H256>| Log {
+        address: Address::from_low_u64_be(1),
+        topics,
+        data: Bytes(ethabi::encode(&[Token::Uint(100.into())])),
+        block_hash: Some(H256::from_low_u64_be(1)),
+        block_number: Some(U64::from(1)),
+        transaction_hash: Some(tx_hash),
+        transaction_index: Some(U64::zero()),
+        log_index: Some(0.into()),
+        transaction_log_index: Some(0.into()),
+        log_type: None,
+        removed: Some(false),
+    };
+    let handlers_for_log = |data_source: &DataSource, log: &Log| {
+        data_source
+            .handlers_for_log(log)
+            .into_iter()
+            .map(|handler| handler.handler.clone())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        vec!["handleTransfer", "handleTransferFrom"],
+        handlers_for_log(&data_source, &log(vec![topic0, from, to]))
+    );
+    assert_eq!(
+        vec!["handleTransfer"],
+        handlers_for_log(&data_source, &log(vec![topic0, to, from]))
+    );
+    assert!(handlers_for_log(&data_source, &log(vec![])).is_empty());
+
+    // A data source for another contract with the same handlers only
+    // matches its own logs
+    let mut other = data_source.clone();
+    other.address = Some(Address::from_low_u64_be(2));
+    let match_log = |data_source: &DataSource| {
+        blockchain::DataSource::match_and_decode(
+            data_source,
+            &EthereumTrigger::Log(LogRef::FullLog(Arc::new(log(vec![topic0, to, from])), None)),
+            &block,
+            &logger,
+        )
+        .unwrap()
+    };
+    assert_eq!(
+        "handleTransfer",
+        match_log(&data_source).unwrap().handler_name()
+    );
+    assert!(match_log(&other).is_none());
+}
+
+#[test]
+fn declared_file_sources_are_created_from_event_params() {
+    let logger = Logger::root(slog::Discard, o!());
+    let mut data_source = transfer_data_source(API_VERSION_0_0_9);
+    data_source.mapping.event_handlers = EventHandlers::new(vec![MappingEventHandler {
+        event: "MetadataSet(indexed uint256,bytes32,string)".to_string(),
+        topic0: None,
+        topic1: None,
+        topic2: None,
+        topic3: None,
+        handler: "handleMetadataSet".to_string(),
+        receipt: false,
+        calls: CallDecls::default(),
+        file_sources: vec![
+            FileSourceDecl {
+                template: "Metadata".to_string(),
+                param: "digest".to_string(),
+                transform: FileSourceTransform::Cidv0FromBytes32,
+            },
+            FileSourceDecl {
+                template: "Metadata".to_string(),
+                param: "uri".to_string(),
+                transform: FileSourceTransform::Raw,
+            },
+            // A `uint256` can't be used as is, the host skips it
+            FileSourceDecl {
+                template: "Metadata".to_string(),
+                param: "id".to_string(),
+                transform: FileSourceTransform::Raw,
+            },
+        ],
+        dedupe: false,
+    }]);
+
+    let tx_hash = H256::from_low_u64_be(7);
+    let mut block = LightEthereumBlock::default();
+    block.number = Some(U64::from(1));
+    block.hash = Some(H256::from_low_u64_be(1));
+    block.transactions.push(Transaction {
+        hash: tx_hash,
+        ..Transaction::default()
+    });
+    let block = Arc::new(BlockFinality::Final(Arc::new(block)));
+
+    let digest =
+        hex::decode("59948439065f29619ef41280cbb932be52c56d99c5966b65e0111239f098bbef").unwrap();
+    let log = Log {
+        address: Address::from_low_u64_be(1),
+        topics: vec![
+            data_source.mapping.event_handlers[0].topic0(),
+            H256::from_low_u64_be(42),
+        ],
+        data: Bytes(ethabi::encode(&[
+            Token::FixedBytes(digest),
+            Token::String("QmVkvoPGi9jvvuxsHDVJDgzPEzagBaWSZRYoRDzU244HjZ".to_string()),
+        ])),
+        block_hash: Some(H256::from_low_u64_be(1)),
+        block_number: Some(U64::from(1)),
+        transaction_hash: Some(tx_hash),
+        transaction_index: Some(U64::zero()),
+        log_index: Some(0.into()),
+        transaction_log_index: Some(0.into()),
+        log_type: None,
+        removed: Some(false),
+    };
+
+    let trigger = blockchain::DataSource::match_and_decode(
+        &data_source,
+        &EthereumTrigger::Log(LogRef::FullLog(Arc::new(log), None)),
+        &block,
+        &logger,
+    )
+    .unwrap()
+    .unwrap();
+
+    assert_eq!(
+        trigger.trigger.declared_file_sources(),
+        &[
+            DeclaredFileSource {
+                template: "Metadata".to_string(),
+                source: Some("QmUNLLsPACCz1vLxQVkXqqLX5R1X345qqfHbsf67hvA3Nn".to_string()),
+            },
+            DeclaredFileSource {
+                template: "Metadata".to_string(),
+                source: Some("QmVkvoPGi9jvvuxsHDVJDgzPEzagBaWSZRYoRDzU244HjZ".to_string()),
+            },
+            DeclaredFileSource {
+                template: "Metadata".to_string(),
+                source: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn duplicate_log_triggers_keep_the_earliest_data_source() {
+    let handler = |handler: &str, dedupe: bool| MappingEventHandler {
+        event: "Transfer(indexed address,indexed address,uint256)".to_string(),
+        topic0: None,
+        topic1: None,
+        topic2: None,
+        topic3: None,
+        handler: handler.to_string(),
+        receipt: false,
+        calls: CallDecls::default(),
+        file_sources: vec![],
+        dedupe,
+    };
+    let transfer = handler("handleTransfer", false);
+    let transfer_dedupe = handler("handleTransfer", true);
+    let other = handler("handleOtherTransfer", true);
+
+    // A template data source created at block 10 duplicates the static
+    // data source, regardless of the order of their triggers
+    assert_eq!(
+        vec![true, false],
+        duplicate_log_triggers(&[Some((&transfer_dedupe, Some(10))), Some((&transfer, None))])
+    );
+    assert_eq!(
+        vec![false, true, true],
+        duplicate_log_triggers(&[
+            Some((&transfer, Some(5))),
+            Some((&transfer_dedupe, Some(10))),
+            Some((&transfer_dedupe, Some(5))),
+        ])
+    );
+
+    // Distinct handlers for the same log are never duplicates
+    assert_eq!(
+        vec![false, false, false],
+        duplicate_log_triggers(&[Some((&transfer, None)), Some((&other, Some(10))), None])
+    );
+}
+
+/// A host for `data_source` that only decodes triggers
+struct DecodeOnlyHost {
+    data_source: data_source::DataSource<Chain>,
+    metrics: Arc<HostMetrics>,
+}
+
+#[async_trait]
+impl RuntimeHost<Chain> for DecodeOnlyHost {
+    fn data_source(&self) -> &data_source::DataSource<Chain> {
+        &self.data_source
+    }
+
+    fn match_and_decode(
+        &self,
+        trigger: &data_source::TriggerData<Chain>,
+        block: &Arc<BlockFinality>,
+        logger: &Logger,
+    ) -> Result<Option<data_source::TriggerWithHandler<data_source::MappingTrigger<Chain>>>, Error>
+    {
+        self.data_source.match_and_decode(trigger, block, logger)
+    }
+
+    async fn process_block(
+        &self,
+        _: &Logger,
+        _: BlockPtr,
+        _: BlockTime,
+        _: Box<[u8]>,
+        _: String,
+        _: BlockState,
+        _: SharedProofOfIndexing,
+        _: &Option<Arc<dyn SubgraphFork>>,
+        _: bool,
+    ) -> Result<BlockState, MappingError> {
+        unimplemented!()
+    }
+
+    async fn process_mapping_trigger(
+        &self,
+        _: &Logger,
+        _: data_source::TriggerWithHandler<data_source::MappingTrigger<Chain>>,
+        _: BlockState,
+        _: SharedProofOfIndexing,
+        _: &Option<Arc<dyn SubgraphFork>>,
+        _: bool,
+    ) -> Result<BlockState, MappingError> {
+        unimplemented!()
+    }
+
+    fn creation_block_number(&self) -> Option<BlockNumber> {
+        self.data_source.creation_block()
+    }
+
+    fn done_at(&self) -> Option<BlockNumber> {
+        None
+    }
+
+    fn set_done_at(&self, _: Option<BlockNumber>) {}
+
+    fn host_metrics(&self) -> Arc<HostMetrics> {
+        self.metrics.cheap_clone()
+    }
+}
+
+/// A call cache for tests that make no eth calls
+struct NoCallCache;
+
+impl EthereumCallCache for NoCallCache {
+    fn get_call(&self, _: &call::Request, _: BlockPtr) -> Result<Option<call::Response>, Error> {
+        unreachable!()
+    }
+
+    fn get_calls(
+        &self,
+        _: &[call::Request],
+        _: BlockPtr,
+    ) -> Result<(Vec<call::Response>, Vec<call::Request>), Error> {
+        unreachable!()
+    }
+
+    fn get_calls_in_block(&self, _: BlockPtr) -> Result<Vec<CachedEthereumCall>, Error> {
+        unreachable!()
+    }
+
+    fn set_call(
+        &self,
+        _: &Logger,
+        _: call::Request,
+        _: BlockPtr,
+        _: call::Retval,
+    ) -> Result<(), Error> {
+        unreachable!()
+    }
+
+    fn set_calls(
+        &self,
+        _: &Logger,
+        _: Vec<(call::Request, call::Retval)>,
+        _: BlockPtr,
+    ) -> Result<(), Error> {
+        unreachable!()
+    }
+}
+
+/// A drain that keeps the messages of all records
+#[derive(Clone, Default)]
+struct Messages(Arc<Mutex<Vec<String>>>);
+
+impl slog::Drain for Messages {
+    type Ok = ();
+    type Err = slog::Never;
+
+    fn log(&self, record: &slog::Record, _: &slog::OwnedKVList) -> Result<(), slog::Never> {
+        self.0.lock().unwrap().push(record.msg().to_string());
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn duplicate_log_triggers_are_logged_once_per_block() {
+    let messages = Messages::default();
+    let logger = Logger::root(messages.clone(), o!());
+    let registry = Arc::new(MetricsRegistry::mock());
+    let stopwatch = StopwatchMetrics::new(
+        logger.clone(),
+        DeploymentHash::new("QmDedupTest").unwrap(),
+        "test",
+        registry.clone(),
+        "primary".to_string(),
+    );
+    let host_metrics = Arc::new(HostMetrics::new(
+        registry.clone(),
+        "QmDedupTest",
+        stopwatch.clone(),
+        GasMetrics::mock(),
+    ));
+    let metrics = Arc::new(SubgraphInstanceMetrics::new(
+        registry,
+        "QmDedupTest",
+        stopwatch,
+    ));
+
+    // A template data source created at block 1 for the address of the
+    // static data source, with the same deduplicated handler
+    let mut token = transfer_data_source(API_VERSION_0_0_9);
+    token.mapping.event_handlers = EventHandlers::new(vec![MappingEventHandler {
+        event: "Transfer(indexed address,indexed address,uint256)".to_string(),
+        topic0: None,
+        topic1: None,
+        topic2: None,
+        topic3: None,
+        handler: "handleTransfer".to_string(),
+        receipt: false,
+        calls: CallDecls::default(),
+        file_sources: vec![],
+        dedupe: true,
+    }]);
+    let mut template = token.clone();
+    template.name = "TokenTemplate".to_string();
+    template.creation_block = Some(1);
+    let topic0 = token.mapping.event_handlers[0].topic0();
+    let hosts: Vec<_> = [token, template]
+        .into_iter()
+        .map(|ds| DecodeOnlyHost {
+            data_source: data_source::DataSource::Onchain(ds),
+            metrics: host_metrics.cheap_clone(),
+        })
+        .collect();
+
+    let tx_hash = H256::from_low_u64_be(7);
+    let mut block = LightEthereumBlock::default();
+    block.number = Some(U64::from(2));
+    block.hash = Some(H256::from_low_u64_be(2));
+    block.transactions.push(Transaction {
+        hash: tx_hash,
+        ..Transaction::default()
+    });
+    let block = Arc::new(BlockFinality::Final(Arc::new(block)));
+
+    let from = H256::from(Address::from_low_u64_be(2));
+    let to = H256::from(Address::from_low_u64_be(3));
+    let runnables = (0..3u64)
+        .map(|log_index| {
+            let log = Log {
+                address: Address::from_low_u64_be(1),
+                topics: vec![topic0, from, to],
+                data: Bytes(ethabi::encode(&[Token::Uint(100.into())])),
+                block_hash: Some(H256::from_low_u64_be(2)),
+                block_number: Some(U64::from(2)),
+                transaction_hash: Some(tx_hash),
+                transaction_index: Some(U64::zero()),
+                log_index: Some(log_index.into()),
+                transaction_log_index: Some(log_index.into()),
+                log_type: None,
+                removed: Some(false),
+            };
+            let trigger = data_source::TriggerData::Onchain(EthereumTrigger::Log(LogRef::FullLog(
+                Arc::new(log),
+                None,
+            )));
+            let hosted_triggers = hosts
+                .iter()
+                .filter_map(|host| {
+                    let mapping_trigger =
+                        host.match_and_decode(&trigger, &block, &logger).unwrap()?;
+                    Some(HostedTrigger {
+                        host: host as &dyn RuntimeHost<Chain>,
+                        mapping_trigger,
+                    })
+                })
+                .collect();
+            RunnableTriggers {
+                trigger,
+                hosted_triggers,
+                decode_errors: vec![],
+            }
+        })
+        .collect();
+
+    let hook = DecoderHook::new(
+        Arc::new(EthereumNetworkAdapters::for_testing(vec![], vec![]).await),
+        Arc::new(NoCallCache),
+        None,
+    );
+    let runnables =
+        blockchain::DecoderHook::after_decode(&hook, &logger, &block.ptr(), runnables, &metrics)
+            .await
+            .unwrap();
+
+    // Only the static data source handles the logs
+    for runnable in &runnables {
+        let names: Vec<_> = runnable
+            .hosted_triggers
+            .iter()
+            .map(|hosted| hosted.host.data_source().name())
+            .collect();
+        assert_eq!(vec!["Token"], names);
+    }
+
+    let messages = messages.0.lock().unwrap();
+    assert_eq!(
+        1,
+        messages
+            .iter()
+            .filter(|msg| msg.as_str()
+                == "Logs are handled by the same handler in several data sources")
+            .count()
+    );
+}
+
+#[test]
+fn call_0_0_10_extends_the_0_0_3_layout() {
+    let call_0_0_3 = AscEthereumCall_0_0_3::<AscEthereumTransaction_0_0_6, AscEthereumBlock_0_0_6> {
+        to: AscPtr::new(1),
+        from: AscPtr::new(2),
+        block: AscPtr::new(3),
+        transaction: AscPtr::new(4),
+        inputs: AscPtr::new(5),
+        outputs: AscPtr::new(6),
+    };
+    let call_0_0_10 =
+        AscEthereumCall_0_0_10::<AscEthereumTransaction_0_0_6, AscEthereumBlock_0_0_6> {
+            to: AscPtr::new(1),
+            from: AscPtr::new(2),
+            block: AscPtr::new(3),
+            transaction: AscPtr::new(4),
+            inputs: AscPtr::new(5),
+            outputs: AscPtr::new(6),
+            trace_address: AscPtr::new(7),
+            trace_ordinal: AscPtr::new(8),
+        };
+
+    // Mappings compiled against the older class keep reading the same
+    // fields; the new ones follow them
+    let old = call_0_0_3.to_asc_bytes().unwrap();
+    let new = call_0_0_10.to_asc_bytes().unwrap();
+    assert_eq!(old[..], new[..old.len()]);
+    let tail: Vec<u8> = [7u32, 8u32]
+        .iter()
+        .flat_map(|ptr| ptr.to_le_bytes())
+        .collect();
+    assert_eq!(tail[..], new[old.len()..]);
+}
+
+/// One transaction whose top-level call makes two calls, the first of
+/// which makes another one that self-destructs, and which logs two events;
+/// once as a firehose block and once as the traces and receipt an RPC
+/// provider returns for it. Mappings build entity IDs from the positions,
+/// so they must not depend on the provider
+#[test]
+fn rpc_and_firehose_agree_on_call_and_log_positions() {
+    let block_hash = H256::from_low_u64_be(1);
+    let tx_hash = H256::from_low_u64_be(2);
+    let address = |i: u64| Address::from_low_u64_be(i).as_bytes().to_vec();
+
+    // (index, parent_index, depth, to) for root -> [a -> [c], b]
+    let calls = [(1, 0, 0, 10), (2, 1, 1, 11), (3, 2, 2, 12), (4, 1, 1, 13)];
+    let firehose_block = codec::Block {
+        hash: block_hash.as_bytes().to_vec(),
+        number: 1,
+        header: Some(codec::BlockHeader {
+            parent_hash: vec![0; 32],
+            uncle_hash: vec![0; 32],
+            coinbase: vec![0; 20],
+            state_root: vec![0; 32],
+            transactions_root: vec![0; 32],
+            receipt_root: vec![0; 32],
+            mix_hash: vec![0; 32],
+            ..Default::default()
+        }),
+        transaction_traces: vec![codec::TransactionTrace {
+            hash: tx_hash.as_bytes().to_vec(),
+            from: address(9),
+            to: address(10),
+            status: codec::TransactionTraceStatus::Succeeded as i32,
+            calls: calls
+                .iter()
+                .map(|(index, parent_index, depth, to)| codec::Call {
+                    index: *index,
+                    parent_index: *parent_index,
+                    depth: *depth,
+                    call_type: codec::CallType::Call as i32,
+                    caller: address(9),
+                    address: address(*to),
+                    input: vec![1; 4],
+                    suicide: *index == 3,
+                    ..Default::default()
+                })
+                .collect(),
+            receipt: Some(codec::TransactionReceipt {
+                logs_bloom: vec![0; 256],
+                logs: (0..2)
+                    .map(|index| codec::Log {
+                        address: address(11),
+                        index,
+                        block_index: index + 3,
+                        ..Default::default()
+                    })
+                    .collect(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let firehose_block: EthereumBlockWithCalls = (&firehose_block).try_into().unwrap();
+
+    // Parity reports the self-destruct as a trace of its own
+    let trace = |trace_address: &[usize], to: u64| {
+        json::json!({
+            "action": {
+                "callType": "call",
+                "from": format!("{:?}", Address::from_low_u64_be(9)),
+                "to": format!("{:?}", Address::from_low_u64_be(to)),
+                "gas": "0x0",
+                "input": "0x01010101",
+                "value": "0x0"
+            },
+            "result": { "gasUsed": "0x0", "output": "0x" },
+            "traceAddress": trace_address,
+            "subtraces": 0,
+            "transactionPosition": 0,
+            "transactionHash": format!("{:?}", tx_hash),
+            "blockNumber": 1,
+            "blockHash": format!("{:?}", block_hash),
+            "type": "call"
+        })
+    };
+    let suicide = json::json!({
+        "action": {
+            "address": format!("{:?}", Address::from_low_u64_be(12)),
+            "refundAddress": format!("{:?}", Address::from_low_u64_be(9)),
+            "balance": "0x0"
+        },
+        "result": null,
+        "traceAddress": [0, 0, 0],
+        "subtraces": 0,
+        "transactionPosition": 0,
+        "transactionHash": format!("{:?}", tx_hash),
+        "blockNumber": 1,
+        "blockHash": format!("{:?}", block_hash),
+        "type": "suicide"
+    });
+    let traces: Vec<Trace> = json::from_value(json::json!([
+        trace(&[], 10),
+        trace(&[0], 11),
+        trace(&[0, 0], 12),
+        suicide,
+        trace(&[1], 13)
+    ]))
+    .unwrap();
+    let mut rpc_calls: Vec<_> = traces
+        .iter()
+        .filter_map(EthereumCall::try_from_trace)
+        .collect();
+    set_trace_ordinals(rpc_calls.iter_mut(), &traces);
+
+    let positions = |calls: &[EthereumCall]| {
+        calls
+            .iter()
+            .map(|call| (call.to, call.trace_address.clone(), call.trace_ordinal))
+            .collect::<Vec<_>>()
+    };
+    let firehose_calls = firehose_block.calls.as_ref().unwrap();
+    assert_eq!(
+        vec![
+            (Address::from_low_u64_be(10), vec![], Some(0)),
+            (Address::from_low_u64_be(11), vec![0], Some(1)),
+            (Address::from_low_u64_be(12), vec![0, 0], Some(2)),
+            (Address::from_low_u64_be(13), vec![1], Some(3)),
+        ],
+        positions(firehose_calls)
+    );
+    assert_eq!(positions(firehose_calls), positions(&rpc_calls));
+
+    // Providers like geth return neither logs nor receipts with the index
+    // of a log within its transaction
+    let firehose_receipt = firehose_block.ethereum_block.transaction_receipts[0].as_ref();
+    let mut rpc_receipt = firehose_receipt.clone();
+    for log in rpc_receipt.logs.iter_mut() {
+        log.transaction_log_index = None;
+    }
+    let log_positions = |receipt: &TransactionReceipt| {
+        receipt
+            .logs
+            .iter()
+            .map(|log| (log.log_index, transaction_log_index(log, Some(receipt))))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        vec![
+            (Some(3.into()), Some(0.into())),
+            (Some(4.into()), Some(1.into()))
+        ],
+        log_positions(firehose_receipt)
+    );
+    assert_eq!(log_positions(firehose_receipt), log_positions(&rpc_receipt));
+}
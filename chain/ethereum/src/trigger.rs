@@ -1,5 +1,7 @@
+use graph::blockchain::DeclaredFileSource;
 use graph::blockchain::MappingTriggerTrait;
 use graph::blockchain::TriggerData;
+use graph::data::subgraph::API_VERSION_0_0_10;
 use graph::data::subgraph::API_VERSION_0_0_2;
 use graph::data::subgraph::API_VERSION_0_0_6;
 use graph::data::subgraph::API_VERSION_0_0_7;
@@ -28,10 +30,12 @@ use graph_runtime_wasm::module::ToAscPtr;
 use std::ops::Deref;
 use std::{cmp::Ordering, sync::Arc};
 
+use crate::adapter::FunctionSelector;
 use crate::data_source::DeclaredCall;
 use crate::runtime::abi::AscEthereumBlock;
 use crate::runtime::abi::AscEthereumBlock_0_0_6;
 use crate::runtime::abi::AscEthereumCall;
+use crate::runtime::abi::AscEthereumCall_0_0_10;
 use crate::runtime::abi::AscEthereumCall_0_0_3;
 use crate::runtime::abi::AscEthereumEvent;
 use crate::runtime::abi::AscEthereumEvent_0_0_7;
@@ -50,6 +54,7 @@ pub enum MappingTrigger {
         params: Vec<LogParam>,
         receipt: Option<Arc<TransactionReceipt>>,
         calls: Vec<DeclaredCall>,
+        file_sources: Vec<DeclaredFileSource>,
     },
     Call {
         block: Arc<LightEthereumBlock>,
@@ -57,6 +62,8 @@ pub enum MappingTrigger {
         call: Arc<EthereumCall>,
         inputs: Vec<LogParam>,
         outputs: Vec<LogParam>,
+        /// The trace ordinal of `call` if the handler asked for it
+        trace_ordinal: Option<u32>,
     },
     Block {
         block: Arc<LightEthereumBlock>,
@@ -76,6 +83,13 @@ impl MappingTriggerTrait for MappingTrigger {
             None => String::new(),
         }
     }
+
+    fn declared_file_sources(&self) -> &[DeclaredFileSource] {
+        match self {
+            MappingTrigger::Log { file_sources, .. } => file_sources,
+            MappingTrigger::Call { .. } | MappingTrigger::Block { .. } => &[],
+        }
+    }
 }
 
 // Logging the block is too verbose, so this strips the block from the trigger for Debug.
@@ -105,6 +119,7 @@ impl std::fmt::Debug for MappingTrigger {
                 params,
                 receipt: _,
                 calls: _,
+                file_sources: _,
             } => MappingTriggerWithoutBlock::Log {
                 _transaction: transaction.cheap_clone(),
                 _log: log.cheap_clone(),
@@ -116,6 +131,7 @@ impl std::fmt::Debug for MappingTrigger {
                 call,
                 inputs,
                 outputs,
+                trace_ordinal: _,
             } => MappingTriggerWithoutBlock::Call {
                 _transaction: transaction.cheap_clone(),
                 _call: call.cheap_clone(),
@@ -143,14 +159,23 @@ impl ToAscPtr for MappingTrigger {
                 params,
                 receipt,
                 calls: _,
+                file_sources: _,
             } => {
                 let api_version = heap.api_version();
+                let log_index = log.log_index.unwrap_or(U256::zero());
+                // Before apiVersion 0.0.10, `transactionLogIndex` was the
+                // log index within the block
+                let transaction_log_index = if api_version >= API_VERSION_0_0_10 {
+                    transaction_log_index(&log, receipt.as_deref()).unwrap_or(log_index)
+                } else {
+                    log_index
+                };
                 let ethereum_event_data = EthereumEventData {
                     block: EthereumBlockData::from(block.as_ref()),
                     transaction: EthereumTransactionData::from(transaction.deref()),
                     address: log.address,
-                    log_index: log.log_index.unwrap_or(U256::zero()),
-                    transaction_log_index: log.log_index.unwrap_or(U256::zero()),
+                    log_index,
+                    transaction_log_index,
                     log_type: log.log_type.clone(),
                     params,
                 };
@@ -193,6 +218,7 @@ impl ToAscPtr for MappingTrigger {
                 call,
                 inputs,
                 outputs,
+                trace_ordinal,
             } => {
                 let call = EthereumCallData {
                     to: call.to,
@@ -201,8 +227,20 @@ impl ToAscPtr for MappingTrigger {
                     transaction: EthereumTransactionData::from(transaction.deref()),
                     inputs,
                     outputs,
+                    trace_address: call.trace_address.clone(),
+                    trace_ordinal,
                 };
-                if heap.api_version() >= Version::new(0, 0, 6) {
+                if heap.api_version() >= API_VERSION_0_0_10 {
+                    asc_new::<
+                        AscEthereumCall_0_0_10<
+                            AscEthereumTransaction_0_0_6,
+                            AscEthereumBlock_0_0_6,
+                        >,
+                        _,
+                        _,
+                    >(heap, &call, gas)?
+                    .erase()
+                } else if heap.api_version() >= Version::new(0, 0, 6) {
                     asc_new::<
                         AscEthereumCall_0_0_3<AscEthereumTransaction_0_0_6, AscEthereumBlock_0_0_6>,
                         _,
@@ -278,6 +316,23 @@ impl LogRef {
     }
 }
 
+/// The position of `log` among the logs of its transaction. Only some
+/// providers include it with the log; the receipt has it for all of them
+pub(crate) fn transaction_log_index(
+    log: &Log,
+    receipt: Option<&TransactionReceipt>,
+) -> Option<U256> {
+    receipt
+        .and_then(|receipt| {
+            receipt
+                .logs
+                .iter()
+                .position(|l| l.log_index == log.log_index)
+        })
+        .map(U256::from)
+        .or(log.transaction_log_index)
+}
+
 #[derive(Clone, Debug)]
 pub enum EthereumTrigger {
     Block(BlockPtr, EthereumBlockTriggerType),
@@ -308,7 +363,11 @@ impl Eq for EthereumTrigger {}
 pub enum EthereumBlockTriggerType {
     Start,
     End,
-    WithCallTo(Address),
+    /// The block contains calls to `Address`; the selectors of the
+    /// functions that were called are listed without duplicates
+    WithCallTo(Address, Vec<FunctionSelector>),
+    /// Data sources whose `endBlock` is this block have reached their end.
+    DataSourceEnd,
 }
 
 impl EthereumTrigger {
@@ -333,62 +392,74 @@ impl EthereumTrigger {
     /// `None` means the trigger matches any address.
     pub fn address(&self) -> Option<&Address> {
         match self {
-            EthereumTrigger::Block(_, EthereumBlockTriggerType::WithCallTo(address)) => {
+            EthereumTrigger::Block(_, EthereumBlockTriggerType::WithCallTo(address, _)) => {
                 Some(address)
             }
             EthereumTrigger::Call(call) => Some(&call.to),
             EthereumTrigger::Log(log_ref) => Some(&log_ref.address()),
             // Unfiltered block triggers match any data source address.
             EthereumTrigger::Block(_, EthereumBlockTriggerType::End) => None,
+            EthereumTrigger::Block(_, EthereumBlockTriggerType::DataSourceEnd) => None,
             EthereumTrigger::Block(_, EthereumBlockTriggerType::Start) => None,
         }
     }
 }
 
-impl Ord for EthereumTrigger {
-    fn cmp(&self, other: &Self) -> Ordering {
-        match (self, other) {
-            // Block triggers with `EthereumBlockTriggerType::Start` always come
-            (Self::Block(_, EthereumBlockTriggerType::Start), _) => Ordering::Less,
-            (_, Self::Block(_, EthereumBlockTriggerType::Start)) => Ordering::Greater,
-
-            // Keep the order when comparing two block triggers
-            (Self::Block(..), Self::Block(..)) => Ordering::Equal,
-
-            // Block triggers with `EthereumBlockTriggerType::End` always come last
-            (Self::Block(..), _) => Ordering::Greater,
-            (_, Self::Block(..)) => Ordering::Less,
-
-            // Calls are ordered by their tx indexes
-            (Self::Call(a), Self::Call(b)) => a.transaction_index.cmp(&b.transaction_index),
+/// The position of a trigger within its block. Sorting triggers by this key
+/// gives the order in which they are processed:
+///
+/// 1. block triggers of type `Start`, keeping their relative order
+/// 2. transaction triggers, by transaction index; within a transaction,
+///    events come first, ordered by log index, followed by calls, ordered
+///    lexicographically by trace address
+/// 3. all other block triggers, except `DataSourceEnd`, keeping their
+///    relative order
+/// 4. block triggers of type `DataSourceEnd`
+///
+/// Triggers from RPC and firehose produce the same key, so the order does
+/// not depend on where the block came from.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TriggerOrder<'a> {
+    BlockStart,
+    Transaction {
+        transaction_index: u64,
+        position: TransactionTriggerPosition<'a>,
+    },
+    BlockEnd,
+    DataSourceEnd,
+}
 
-            // Events are ordered by their log index
-            (Self::Log(a), Self::Log(b)) => a.log_index().cmp(&b.log_index()),
+/// The position of a trigger within its transaction; see [`TriggerOrder`].
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TransactionTriggerPosition<'a> {
+    Log(Option<U256>),
+    Call(&'a [usize]),
+}
 
-            // Calls vs. events are logged by their tx index;
-            // if they are from the same transaction, events come first
-            (Self::Call(a), Self::Log(b))
-                if a.transaction_index == b.transaction_index().unwrap().as_u64() =>
-            {
-                Ordering::Greater
-            }
-            (Self::Log(a), Self::Call(b))
-                if a.transaction_index().unwrap().as_u64() == b.transaction_index =>
-            {
-                Ordering::Less
-            }
-            (Self::Call(a), Self::Log(b)) => a
-                .transaction_index
-                .cmp(&b.transaction_index().unwrap().as_u64()),
-            (Self::Log(a), Self::Call(b)) => a
-                .transaction_index()
-                .unwrap()
-                .as_u64()
-                .cmp(&b.transaction_index),
+impl EthereumTrigger {
+    pub fn order(&self) -> TriggerOrder<'_> {
+        match self {
+            Self::Block(_, EthereumBlockTriggerType::Start) => TriggerOrder::BlockStart,
+            Self::Block(_, EthereumBlockTriggerType::DataSourceEnd) => TriggerOrder::DataSourceEnd,
+            Self::Block(..) => TriggerOrder::BlockEnd,
+            Self::Log(log) => TriggerOrder::Transaction {
+                transaction_index: log.transaction_index().unwrap().as_u64(),
+                position: TransactionTriggerPosition::Log(log.log_index()),
+            },
+            Self::Call(call) => TriggerOrder::Transaction {
+                transaction_index: call.transaction_index,
+                position: TransactionTriggerPosition::Call(&call.trace_address),
+            },
         }
     }
 }
 
+impl Ord for EthereumTrigger {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.order().cmp(&other.order())
+    }
+}
+
 impl PartialOrd for EthereumTrigger {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
@@ -515,4 +586,6 @@ pub struct EthereumCallData {
     pub transaction: EthereumTransactionData,
     pub inputs: Vec<LogParam>,
     pub outputs: Vec<LogParam>,
+    pub trace_address: Vec<usize>,
+    pub trace_ordinal: Option<u32>,
 }
@@ -12,7 +12,10 @@ use graph::components::store::{EmptyStore, GetScope, ReadStore, StoredDynamicDat
 use graph::components::subgraph::InstanceDSTemplate;
 use graph::components::{
     store::ModificationsAndCache,
-    subgraph::{MappingError, PoICausalityRegion, ProofOfIndexing, SharedProofOfIndexing},
+    subgraph::{
+        DeterministicDecodeError, MappingError, PoICausalityRegion, ProofOfIndexing,
+        SharedProofOfIndexing,
+    },
 };
 use graph::data::store::scalar::Bytes;
 use graph::data::subgraph::{
@@ -335,6 +338,18 @@ where
         match match_res {
             Ok(runnables) => {
                 for runnable in runnables {
+                    let mut state = res.unwrap();
+                    for error in runnable.decode_errors {
+                        self.record_deterministic_decode_error(
+                            &logger,
+                            &mut state,
+                            &block_ptr,
+                            &proof_of_indexing,
+                            &causality_region,
+                            &runnable.trigger,
+                            error,
+                        );
+                    }
                     let process_res = self
                         .ctx
                         .trigger_processor
@@ -342,7 +357,7 @@ where
                             &self.logger,
                             runnable.hosted_triggers,
                             &block,
-                            res.unwrap(),
+                            state,
                             &proof_of_indexing,
                             &causality_region,
                             &self.inputs.debug_fork,
@@ -492,6 +507,18 @@ where
                 match match_res {
                     Ok(runnables) => {
                         for runnable in runnables {
+                            let mut state = res.unwrap();
+                            for error in runnable.decode_errors {
+                                self.record_deterministic_decode_error(
+                                    &logger,
+                                    &mut state,
+                                    &block_ptr,
+                                    &proof_of_indexing,
+                                    &causality_region,
+                                    &runnable.trigger,
+                                    error,
+                                );
+                            }
                             let process_res = self
                                 .ctx
                                 .trigger_processor
@@ -499,7 +526,7 @@ where
                                     &self.logger,
                                     runnable.hosted_triggers,
                                     &block,
-                                    res.unwrap(),
+                                    state,
                                     &proof_of_indexing,
                                     &causality_region,
                                     &self.inputs.debug_fork,
@@ -536,6 +563,10 @@ where
             }
         }
 
+        // Nothing else runs for this block, so whatever the decoder hook
+        // held back for it, like eth_call results, can be written now
+        self.ctx.decoder.block_done(&block_ptr);
+
         let has_errors = block_state.has_errors();
         let is_non_fatal_errors_active = self
             .inputs
@@ -953,6 +984,47 @@ where
         }
     }
 
+    /// Records that `trigger` could not be decoded for the handler of
+    /// `error` in a way that every indexer runs into. Like a deterministic
+    /// error in a handler, it is added to the block state and the PoI, so
+    /// that the block is transacted with the error instead of being retried.
+    fn record_deterministic_decode_error(
+        &self,
+        logger: &Logger,
+        block_state: &mut BlockState,
+        block_ptr: &BlockPtr,
+        proof_of_indexing: &SharedProofOfIndexing,
+        causality_region: &str,
+        trigger: &TriggerData<C>,
+        error: DeterministicDecodeError,
+    ) {
+        let DeterministicDecodeError { handler, error } = error;
+        let error_context = trigger.error_context();
+        let error = if error_context.is_empty() {
+            error
+        } else {
+            error.context(error_context)
+        };
+        let message = format!("{:#}", error).replace('\n', "\t");
+
+        error!(logger, "Handler skipped because its trigger could not be decoded";
+            "handler" => &handler,
+            "error" => &message,
+        );
+        if let Some(proof_of_indexing) = proof_of_indexing {
+            let mut proof_of_indexing = proof_of_indexing.borrow_mut();
+            proof_of_indexing.start_handler(causality_region);
+            proof_of_indexing.write_deterministic_error(logger, causality_region);
+        }
+        block_state.deterministic_errors.push(SubgraphError {
+            subgraph_id: self.inputs.deployment.hash.clone(),
+            message,
+            block_ptr: Some(block_ptr.clone()),
+            handler: Some(handler),
+            deterministic: true,
+        });
+    }
+
     /// We consider a subgraph caught up when it's at most 10 blocks behind the chain head.
     async fn is_caught_up(&mut self, block_ptr: &BlockPtr) -> Result<bool, Error> {
         const CAUGHT_UP_DISTANCE: BlockNumber = 10;
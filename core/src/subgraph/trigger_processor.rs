@@ -1,15 +1,19 @@
 use async_trait::async_trait;
-use graph::blockchain::{Block, Blockchain, DecoderHook as _};
+use graph::blockchain::{Block, BlockPtr, Blockchain, DecoderHook as _};
 use graph::cheap_clone::CheapClone;
 use graph::components::store::SubgraphFork;
-use graph::components::subgraph::{MappingError, SharedProofOfIndexing};
+use graph::components::subgraph::{DeterministicDecodeError, MappingError, SharedProofOfIndexing};
 use graph::components::trigger_processor::{HostedTrigger, RunnableTriggers};
 use graph::data_source::TriggerData;
+use graph::prelude::tokio::runtime::{Handle, RuntimeFlavor};
+use graph::prelude::tokio::task::block_in_place;
 use graph::prelude::tokio::time::Instant;
 use graph::prelude::{
-    BlockState, RuntimeHost, RuntimeHostBuilder, SubgraphInstanceMetrics, TriggerProcessor,
+    lazy_static, BlockState, RuntimeHost, RuntimeHostBuilder, SubgraphInstanceMetrics,
+    TriggerProcessor, ENV_VARS,
 };
-use graph::slog::Logger;
+use graph::slog::{o, warn, Logger, SendSyncRefUnwindSafeKV};
+use rayon::prelude::*;
 use std::marker::PhantomData;
 use std::sync::Arc;
 
@@ -99,6 +103,8 @@ where
     T: RuntimeHostBuilder<C>,
 {
     hook: C::DecoderHook,
+    /// The most workers used to decode the triggers of one block
+    decode_parallelism: usize,
     _builder: PhantomData<T>,
 }
 
@@ -110,6 +116,7 @@ where
     pub fn new(hook: C::DecoderHook) -> Self {
         Decoder {
             hook,
+            decode_parallelism: ENV_VARS.mappings.decode_parallelism,
             _builder: PhantomData,
         }
     }
@@ -122,47 +129,108 @@ impl<C: Blockchain, T: RuntimeHostBuilder<C>> Decoder<C, T> {
         block: &Arc<C::Block>,
         trigger: &TriggerData<C>,
         hosts: Box<dyn Iterator<Item = &'a T::Host> + Send + 'a>,
-        subgraph_metrics: &Arc<SubgraphInstanceMetrics>,
-    ) -> Result<Vec<HostedTrigger<'a, C>>, MappingError> {
+        subgraph_metrics: &SubgraphInstanceMetrics,
+    ) -> Result<(Vec<HostedTrigger<'a, C>>, Vec<DeterministicDecodeError>), MappingError> {
         let mut host_mapping = vec![];
+        let mut decode_errors = vec![];
 
-        {
-            let _section = subgraph_metrics.stopwatch.start_section("match_and_decode");
+        for host in hosts {
+            let start = Instant::now();
+            let result = host.match_and_decode(trigger, block, logger);
+            let elapsed = start.elapsed();
+            if host.host_metrics().observe_trigger_decode_time(elapsed) {
+                // Only a decoded trigger knows its handler and extras like
+                // the transaction hash and log index
+                let decoded = result.as_ref().ok().and_then(Option::as_ref);
+                let extras = decoded.map_or_else(
+                    || Arc::new(o!()) as Arc<dyn SendSyncRefUnwindSafeKV>,
+                    |trigger| trigger.logging_extras(),
+                );
+                warn!(logger, "Matching and decoding a trigger was slow";
+                    &extras,
+                    "data_source" => host.data_source().name(),
+                    "handler" => decoded.map(|trigger| trigger.handler_name()),
+                    "trigger" => trigger.error_context(),
+                    "elapsed_ms" => elapsed.as_millis(),
+                );
+            }
 
-            for host in hosts {
-                let mapping_trigger = match host.match_and_decode(trigger, block, logger)? {
-                    // Trigger matches and was decoded as a mapping trigger.
-                    Some(mapping_trigger) => mapping_trigger,
+            let mapping_trigger = match result {
+                // Trigger matches and was decoded as a mapping trigger.
+                Ok(Some(mapping_trigger)) => mapping_trigger,
 
-                    // Trigger does not match, do not process it.
-                    None => continue,
-                };
+                // Trigger does not match, do not process it.
+                Ok(None) => continue,
 
-                host_mapping.push(HostedTrigger {
-                    host,
-                    mapping_trigger,
-                });
-            }
+                Err(e) => {
+                    let data_source = host.data_source().name();
+                    match e.downcast::<DeterministicDecodeError>() {
+                        // Only this host misses the trigger, the runner
+                        // records the error for its handler
+                        Ok(e) => {
+                            subgraph_metrics.observe_trigger_decode_failure(
+                                data_source,
+                                Some(&e.handler),
+                                block.number(),
+                            );
+                            decode_errors.push(e);
+                            continue;
+                        }
+                        Err(e) => {
+                            subgraph_metrics.observe_trigger_decode_failure(
+                                data_source,
+                                None,
+                                block.number(),
+                            );
+                            return Err(MappingError::Unknown(e));
+                        }
+                    }
+                }
+            };
+
+            host_mapping.push(HostedTrigger {
+                host,
+                mapping_trigger,
+            });
         }
-        Ok(host_mapping)
+        Ok((host_mapping, decode_errors))
     }
 
-    pub(crate) fn match_and_decode<'a>(
+    fn decode<'a>(
         &'a self,
         logger: &Logger,
         block: &Arc<C::Block>,
         trigger: TriggerData<C>,
         hosts: Box<dyn Iterator<Item = &'a T::Host> + Send + 'a>,
-        subgraph_metrics: &Arc<SubgraphInstanceMetrics>,
+        subgraph_metrics: &SubgraphInstanceMetrics,
     ) -> Result<RunnableTriggers<'a, C>, MappingError> {
         self.match_and_decode_inner(logger, block, &trigger, hosts, subgraph_metrics)
             .map_err(|e| e.add_trigger_context(&trigger))
-            .map(|hosted_triggers| RunnableTriggers {
+            .map(|(hosted_triggers, decode_errors)| RunnableTriggers {
                 trigger,
                 hosted_triggers,
+                decode_errors,
             })
     }
 
+    /// Let the decoder hook know that all triggers of the block at
+    /// `block_ptr` have been processed
+    pub(crate) fn block_done(&self, block_ptr: &BlockPtr) {
+        self.hook.block_done(block_ptr);
+    }
+
+    pub(crate) fn match_and_decode<'a>(
+        &'a self,
+        logger: &Logger,
+        block: &Arc<C::Block>,
+        trigger: TriggerData<C>,
+        hosts: Box<dyn Iterator<Item = &'a T::Host> + Send + 'a>,
+        subgraph_metrics: &Arc<SubgraphInstanceMetrics>,
+    ) -> Result<RunnableTriggers<'a, C>, MappingError> {
+        let _section = subgraph_metrics.stopwatch.start_section("match_and_decode");
+        self.decode(logger, block, trigger, hosts, subgraph_metrics)
+    }
+
     pub(crate) async fn match_and_decode_many<'a, F>(
         &'a self,
         logger: &Logger,
@@ -174,16 +242,491 @@ impl<C: Blockchain, T: RuntimeHostBuilder<C>> Decoder<C, T> {
     where
         F: Fn(&TriggerData<C>) -> Box<dyn Iterator<Item = &'a T::Host> + Send + 'a>,
     {
-        let mut runnables = vec![];
-        for trigger in triggers {
-            let hosts = hosts_filter(&trigger);
-            match self.match_and_decode(logger, block, trigger, hosts, metrics) {
-                Ok(runnable_triggers) => runnables.push(runnable_triggers),
-                Err(e) => return Err(e),
-            }
-        }
+        let triggers: Vec<_> = triggers
+            .map(|trigger| {
+                let hosts = hosts_filter(&trigger);
+                (trigger, hosts)
+            })
+            .collect();
+        let workers = self
+            .decode_parallelism
+            .min(triggers.len() / MIN_TRIGGERS_PER_DECODE_WORKER)
+            .max(1);
+
+        // Decoding happens in trigger order, and the first error in that
+        // order is returned, regardless of how many workers are used.
+        let runnables = {
+            let _section = metrics.stopwatch.start_section("match_and_decode");
+            map_in_order(triggers, workers, |(trigger, hosts)| {
+                self.decode(logger, block, trigger, hosts, metrics)
+            })
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?
+        };
         self.hook
             .after_decode(logger, &block.ptr(), runnables, metrics)
             .await
     }
 }
+
+/// Blocks with fewer triggers than this per worker are not worth the
+/// overhead of handing them to the decode pool.
+const MIN_TRIGGERS_PER_DECODE_WORKER: usize = 64;
+
+lazy_static! {
+    /// The threads that decode the triggers of dense blocks, shared by all
+    /// subgraphs. There are `GRAPH_DECODE_PARALLELISM` of them; the pool is
+    /// only started once a block needs more than one worker.
+    static ref DECODE_POOL: rayon::ThreadPool = rayon::ThreadPoolBuilder::new()
+        .num_threads(ENV_VARS.mappings.decode_parallelism)
+        .thread_name(|i| format!("trigger-decode-{}", i))
+        .build()
+        .expect("failed to start the trigger decode pool");
+}
+
+/// Apply `f` to every item on up to `workers` threads of the decode pool.
+/// The results are returned in the order of `items`. With a single worker,
+/// `f` is applied on the calling thread and stops at the first `Err`,
+/// exactly like a sequential loop would.
+fn map_in_order<I, R, E, F>(items: Vec<I>, workers: usize, f: F) -> Vec<Result<R, E>>
+where
+    I: Send,
+    R: Send,
+    E: Send,
+    F: Fn(I) -> Result<R, E> + Sync,
+{
+    if workers <= 1 || items.len() <= 1 {
+        let mut results = Vec::with_capacity(items.len());
+        for item in items {
+            let result = f(item);
+            let is_err = result.is_err();
+            results.push(result);
+            if is_err {
+                break;
+            }
+        }
+        return results;
+    }
+
+    let chunk_size = (items.len() + workers - 1) / workers;
+    let decode = || {
+        DECODE_POOL.install(|| {
+            items
+                .into_par_iter()
+                .with_min_len(chunk_size)
+                .map(&f)
+                .collect()
+        })
+    };
+
+    // Waiting for the pool blocks this thread; on a multi-threaded runtime,
+    // let tokio move its other tasks elsewhere in the meantime
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            block_in_place(decode)
+        }
+        _ => decode(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use graph::blockchain::mock::{MockBlock, MockBlockchain};
+    use graph::blockchain::{BlockTime, NoopDecoderHook};
+    use graph::components::metrics::gas::GasMetrics;
+    use graph::data_source::{
+        offchain, CausalityRegion, DataSource, MappingTrigger, TriggerWithHandler,
+    };
+    use graph::futures01::sync::mpsc;
+    use graph::ipfs::ContentPath;
+    use graph::prelude::{
+        anyhow, async_trait, o, BlockNumber, DeploymentHash, Error, HostMetrics, Link,
+        MetricsRegistry, Registry, StopwatchMetrics,
+    };
+    use graph::semver;
+    use std::time::Duration;
+
+    type DecodeFn =
+        Box<dyn Fn(&offchain::TriggerData) -> Result<Option<String>, Error> + Send + Sync>;
+
+    /// A host that decodes triggers with `decode`, which returns the name
+    /// of the handler for a matching trigger
+    struct MockHost {
+        data_source: DataSource<MockBlockchain>,
+        decode: DecodeFn,
+        metrics: Arc<HostMetrics>,
+    }
+
+    impl MockHost {
+        fn new(
+            decode: impl Fn(&offchain::TriggerData) -> Result<Option<String>, Error>
+                + Send
+                + Sync
+                + 'static,
+        ) -> Self {
+            let data_source = offchain::DataSource::new(
+                offchain::OffchainDataSourceKind::Ipfs,
+                "mock".into(),
+                0,
+                source(),
+                offchain::Mapping {
+                    language: String::new(),
+                    api_version: semver::Version::new(0, 0, 0),
+                    entities: vec![],
+                    handler: String::new(),
+                    runtime: Arc::new(vec![]),
+                    link: Link {
+                        link: String::new(),
+                    },
+                },
+                Arc::new(None),
+                None,
+                CausalityRegion::ONCHAIN.next(),
+            );
+            MockHost {
+                data_source: DataSource::Offchain(data_source),
+                decode: Box::new(decode),
+                metrics: Arc::new(host_metrics(&Arc::new(MetricsRegistry::mock()))),
+            }
+        }
+
+        fn with_metrics(mut self, metrics: HostMetrics) -> Self {
+            self.metrics = Arc::new(metrics);
+            self
+        }
+    }
+
+    impl PartialEq for MockHost {
+        fn eq(&self, other: &Self) -> bool {
+            std::ptr::eq(self, other)
+        }
+    }
+
+    #[async_trait]
+    impl RuntimeHost<MockBlockchain> for MockHost {
+        fn data_source(&self) -> &DataSource<MockBlockchain> {
+            &self.data_source
+        }
+
+        fn match_and_decode(
+            &self,
+            trigger: &TriggerData<MockBlockchain>,
+            block: &Arc<MockBlock>,
+            _logger: &Logger,
+        ) -> Result<Option<TriggerWithHandler<MappingTrigger<MockBlockchain>>>, Error> {
+            let TriggerData::Offchain(trigger) = trigger else {
+                return Ok(None);
+            };
+            Ok((self.decode)(trigger)?.map(|handler| {
+                TriggerWithHandler::new(
+                    MappingTrigger::Offchain(trigger.clone()),
+                    handler,
+                    block.ptr(),
+                    BlockTime::NONE,
+                )
+            }))
+        }
+
+        async fn process_block(
+            &self,
+            _: &Logger,
+            _: BlockPtr,
+            _: BlockTime,
+            _: Box<[u8]>,
+            _: String,
+            _: BlockState,
+            _: SharedProofOfIndexing,
+            _: &Option<Arc<dyn SubgraphFork>>,
+            _: bool,
+        ) -> Result<BlockState, MappingError> {
+            unimplemented!()
+        }
+
+        async fn process_mapping_trigger(
+            &self,
+            _: &Logger,
+            _: TriggerWithHandler<MappingTrigger<MockBlockchain>>,
+            _: BlockState,
+            _: SharedProofOfIndexing,
+            _: &Option<Arc<dyn SubgraphFork>>,
+            _: bool,
+        ) -> Result<BlockState, MappingError> {
+            unimplemented!()
+        }
+
+        fn creation_block_number(&self) -> Option<BlockNumber> {
+            None
+        }
+
+        fn done_at(&self) -> Option<BlockNumber> {
+            None
+        }
+
+        fn set_done_at(&self, _: Option<BlockNumber>) {}
+
+        fn host_metrics(&self) -> Arc<HostMetrics> {
+            self.metrics.cheap_clone()
+        }
+    }
+
+    #[derive(Clone)]
+    struct MockHostBuilder;
+
+    impl RuntimeHostBuilder<MockBlockchain> for MockHostBuilder {
+        type Host = MockHost;
+        type Req = ();
+
+        fn build(
+            &self,
+            _: String,
+            _: DeploymentHash,
+            _: DataSource<MockBlockchain>,
+            _: Arc<Vec<graph::data_source::DataSourceTemplate<MockBlockchain>>>,
+            _: mpsc::Sender<()>,
+            _: Arc<HostMetrics>,
+        ) -> Result<MockHost, Error> {
+            unimplemented!()
+        }
+
+        fn spawn_mapping(
+            _: &[u8],
+            _: Logger,
+            _: DeploymentHash,
+            _: Arc<HostMetrics>,
+        ) -> Result<mpsc::Sender<()>, Error> {
+            unimplemented!()
+        }
+    }
+
+    fn source() -> offchain::Source {
+        offchain::Source::Ipfs(ContentPath::new(cid::Cid::default().to_string()).unwrap())
+    }
+
+    /// A trigger whose data is `idx`
+    fn trigger(idx: u8) -> TriggerData<MockBlockchain> {
+        TriggerData::Offchain(offchain::TriggerData {
+            source: source(),
+            data: Arc::new(bytes::Bytes::from(vec![idx])),
+        })
+    }
+
+    fn metrics(registry: &Arc<MetricsRegistry>) -> Arc<SubgraphInstanceMetrics> {
+        let logger = Logger::root(graph::slog::Discard, o!());
+        let deployment = DeploymentHash::new("QmDecoderTest").unwrap();
+        let stopwatch = StopwatchMetrics::new(
+            logger,
+            deployment.clone(),
+            "test",
+            registry.clone(),
+            "primary".to_string(),
+        );
+        Arc::new(SubgraphInstanceMetrics::new(
+            registry.clone(),
+            deployment.as_str(),
+            stopwatch,
+        ))
+    }
+
+    fn host_metrics(registry: &Arc<MetricsRegistry>) -> HostMetrics {
+        let logger = Logger::root(graph::slog::Discard, o!());
+        let deployment = DeploymentHash::new("QmDecoderTest").unwrap();
+        let stopwatch = StopwatchMetrics::new(
+            logger,
+            deployment.clone(),
+            "test",
+            registry.clone(),
+            "primary".to_string(),
+        );
+        HostMetrics::new(
+            registry.clone(),
+            deployment.as_str(),
+            stopwatch,
+            GasMetrics::mock(),
+        )
+    }
+
+    /// The handler names of the hosted triggers and of the decode errors
+    /// for one trigger
+    type Decoded = (Vec<String>, Vec<String>);
+
+    /// Match and decode `triggers` against `hosts`, returning the handler
+    /// names for each trigger
+    async fn match_and_decode(
+        hosts: &[MockHost],
+        triggers: Vec<TriggerData<MockBlockchain>>,
+        metrics: &Arc<SubgraphInstanceMetrics>,
+    ) -> Result<Vec<Decoded>, MappingError> {
+        match_and_decode_with(1, hosts, triggers, metrics).await
+    }
+
+    /// Like `match_and_decode`, using up to `decode_parallelism` workers
+    async fn match_and_decode_with(
+        decode_parallelism: usize,
+        hosts: &[MockHost],
+        triggers: Vec<TriggerData<MockBlockchain>>,
+        metrics: &Arc<SubgraphInstanceMetrics>,
+    ) -> Result<Vec<Decoded>, MappingError> {
+        let logger = Logger::root(graph::slog::Discard, o!());
+        let mut decoder = Decoder::<MockBlockchain, MockHostBuilder>::new(NoopDecoderHook);
+        decoder.decode_parallelism = decode_parallelism;
+        let block = Arc::new(MockBlock { number: 1 });
+        let runnables = decoder
+            .match_and_decode_many(
+                &logger,
+                &block,
+                triggers.into_iter(),
+                |_| Box::new(hosts.iter()),
+                metrics,
+            )
+            .await?;
+        Ok(runnables
+            .into_iter()
+            .map(|runnable| {
+                let handlers = runnable
+                    .hosted_triggers
+                    .iter()
+                    .map(|hosted| hosted.mapping_trigger.handler_name().to_string())
+                    .collect();
+                let failed = runnable
+                    .decode_errors
+                    .into_iter()
+                    .map(|e| e.handler)
+                    .collect();
+                (handlers, failed)
+            })
+            .collect())
+    }
+
+    #[tokio::test]
+    async fn deterministic_decode_errors_skip_only_their_trigger() {
+        let registry = Arc::new(MetricsRegistry::mock());
+        let metrics = metrics(&registry);
+
+        let hosts = vec![
+            MockHost::new(|trigger| match trigger.data[0] {
+                1 => Err(DeterministicDecodeError {
+                    handler: "handleFile".to_string(),
+                    error: anyhow!("calldata too short"),
+                }
+                .into()),
+                2 => Err(anyhow!("node is out of memory")),
+                _ => Ok(Some("handleFile".to_string())),
+            }),
+            MockHost::new(|_| Ok(Some("handleOther".to_string()))),
+        ];
+
+        // A trigger that no indexer can decode is reported for its handler,
+        // and every other handler and trigger still runs
+        let decoded = match_and_decode(&hosts, vec![trigger(0), trigger(1), trigger(0)], &metrics)
+            .await
+            .unwrap();
+        let both = vec!["handleFile".to_string(), "handleOther".to_string()];
+        assert_eq!(
+            vec![
+                (both.clone(), vec![]),
+                (
+                    vec!["handleOther".to_string()],
+                    vec!["handleFile".to_string()]
+                ),
+                (both, vec![]),
+            ],
+            decoded
+        );
+
+        // Other errors make the runner retry the block
+        let err = match_and_decode(&hosts, vec![trigger(0), trigger(2)], &metrics)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, MappingError::Unknown(_)), "{:?}", err);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn after_decode_sees_triggers_in_block_order() {
+        let registry = Arc::new(MetricsRegistry::mock());
+        let metrics = metrics(&registry);
+        let hosts = vec![MockHost::new(|trigger| {
+            Ok(Some(format!("handle{}", trigger.data[0])))
+        })];
+
+        // Enough triggers for four workers. `NoopDecoderHook` returns what
+        // it is given, so this is the order `after_decode` sees
+        let triggers: Vec<_> = (0..4 * MIN_TRIGGERS_PER_DECODE_WORKER)
+            .map(|i| trigger((i % 256) as u8))
+            .collect();
+        let expected: Vec<_> = (0..4 * MIN_TRIGGERS_PER_DECODE_WORKER)
+            .map(|i| (vec![format!("handle{}", i % 256)], vec![]))
+            .collect();
+
+        let handlers = match_and_decode_with(4, &hosts, triggers, &metrics)
+            .await
+            .unwrap();
+        assert_eq!(expected, handlers);
+    }
+
+    #[tokio::test]
+    async fn slow_trigger_decodes_are_counted() {
+        let logger = Logger::root(graph::slog::Discard, o!());
+        let registry = Arc::new(Registry::new());
+        let metrics_registry = Arc::new(MetricsRegistry::new(logger, registry.clone()));
+        let metrics = metrics(&metrics_registry);
+        let host_metrics =
+            host_metrics(&metrics_registry).with_slow_decode_threshold(Duration::from_millis(1));
+
+        // Only decoding the second trigger takes longer than the threshold
+        let hosts = vec![MockHost::new(|trigger| {
+            if trigger.data[0] == 1 {
+                std::thread::sleep(Duration::from_millis(5));
+            }
+            Ok(Some("handleFile".to_string()))
+        })
+        .with_metrics(host_metrics)];
+        match_and_decode(&hosts, vec![trigger(0), trigger(1)], &metrics)
+            .await
+            .unwrap();
+
+        let slow = registry
+            .gather()
+            .into_iter()
+            .find(|family| family.get_name() == "deployment_slow_trigger_decodes")
+            .expect("the slow trigger decodes counter is registered");
+        assert_eq!(1.0, slow.get_metric()[0].get_counter().get_value());
+    }
+
+    fn decode(i: usize) -> Result<usize, String> {
+        if i % 97 == 13 {
+            Err(format!("failed to decode {}", i))
+        } else {
+            Ok(i * 2)
+        }
+    }
+
+    #[test]
+    fn map_in_order_matches_sequential_results() {
+        let items: Vec<usize> = (0..1000).map(|i| i * 97).collect();
+        let sequential: Vec<_> = items.iter().cloned().map(decode).collect();
+
+        for workers in [1, 2, 3, 8, 2000] {
+            let parallel = map_in_order(items.clone(), workers, decode);
+            assert_eq!(sequential, parallel, "workers = {}", workers);
+        }
+    }
+
+    #[test]
+    fn map_in_order_reports_first_error() {
+        let items: Vec<usize> = (0..1000).collect();
+        let sequential = items
+            .iter()
+            .cloned()
+            .map(decode)
+            .collect::<Result<Vec<_>, _>>();
+        assert_eq!(Err("failed to decode 13".to_string()), sequential);
+
+        for workers in [1, 2, 3, 8] {
+            let parallel = map_in_order(items.clone(), workers, decode)
+                .into_iter()
+                .collect::<Result<Vec<_>, _>>();
+            assert_eq!(sequential, parallel, "workers = {}", workers);
+        }
+    }
+}
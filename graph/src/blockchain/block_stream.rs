@@ -339,6 +339,23 @@ pub trait FirehoseMapper<C: Blockchain>: Send + Sync {
         endpoint: &Arc<FirehoseEndpoint>,
         block: &C::Block,
     ) -> Result<BlockPtr, Error>;
+
+    /// Returns the Firehose responses for the blocks strictly between `after`
+    /// and `before` that the trigger filter needs even though nothing in them
+    /// matches the filter sent to Firehose, in block order.
+    ///
+    /// Firehose filters can't select blocks by number, so a chain that needs
+    /// specific blocks, like the end blocks of data sources on Ethereum, has
+    /// to fetch them on the side. By default, no blocks are needed.
+    async fn required_blocks_between(
+        &self,
+        _logger: &Logger,
+        _endpoint: &Arc<FirehoseEndpoint>,
+        _after: BlockNumber,
+        _before: BlockNumber,
+    ) -> Result<Vec<firehose::Response>, Error> {
+        Ok(vec![])
+    }
 }
 
 #[async_trait]
@@ -205,6 +205,10 @@ fn stream_blocks<C: Blockchain, F: FirehoseMapper<C>>(
 
     let headers = firehose::ConnectionHeaders::new().with_deployment(deployment.clone());
 
+    // The last block the stream moved to, used to find blocks that the filter
+    // needs but that Firehose skipped over
+    let mut previous_block_num = start_block_num - 1;
+
     // Back off exponentially whenever we encounter a connection error or a stream with bad data
     let mut backoff = ExponentialBackoff::new(Duration::from_millis(500), Duration::from_secs(45));
 
@@ -262,18 +266,27 @@ fn stream_blocks<C: Blockchain, F: FirehoseMapper<C>>(
                             &mut check_subgraph_continuity,
                             manifest_start_block_num,
                             subgraph_current_block.as_ref(),
+                            previous_block_num,
                             mapper.as_ref(),
                             &logger,
                         ).await {
-                            Ok(BlockResponse::Proceed(event, cursor)) => {
+                            Ok(BlockResponse::Proceed(events)) => {
                                 // Reset backoff because we got a good value from the stream
                                 backoff.reset();
 
                                 metrics.observe_response("proceed", &mut last_response_time, &endpoint.provider);
 
-                                yield event;
+                                for (event, cursor) in events {
+                                    previous_block_num = match &event {
+                                        BlockStreamEvent::Revert(parent_ptr, _) => parent_ptr.number,
+                                        BlockStreamEvent::ProcessBlock(block, _) => block.ptr().number,
+                                        BlockStreamEvent::ProcessWasmBlock(block_ptr, ..) => block_ptr.number,
+                                    };
+
+                                    yield event;
 
-                                latest_cursor = FirehoseCursor::from(cursor);
+                                    latest_cursor = FirehoseCursor::from(cursor);
+                                }
                             },
                             Ok(BlockResponse::Rewind(revert_to)) => {
                                 // Reset backoff because we got a good value from the stream
@@ -295,6 +308,7 @@ fn stream_blocks<C: Blockchain, F: FirehoseMapper<C>>(
                                 // and we add + 1 to start block num because Firehose is inclusive and as such,
                                 // we need to move to "next" block.
                                 start_block_num = revert_to.number + 1;
+                                previous_block_num = revert_to.number;
                                 subgraph_current_block = Some(revert_to);
                                 expected_stream_end = true;
                                 break;
@@ -339,7 +353,8 @@ fn stream_blocks<C: Blockchain, F: FirehoseMapper<C>>(
 }
 
 enum BlockResponse<C: Blockchain> {
-    Proceed(BlockStreamEvent<C>, String),
+    /// The events to yield, in order, with the cursor of each
+    Proceed(Vec<(BlockStreamEvent<C>, String)>),
     Rewind(BlockPtr),
 }
 
@@ -349,6 +364,7 @@ async fn process_firehose_response<C: Blockchain, F: FirehoseMapper<C>>(
     check_subgraph_continuity: &mut bool,
     manifest_start_block_num: BlockNumber,
     subgraph_current_block: Option<&BlockPtr>,
+    previous_block_num: BlockNumber,
     mapper: &F,
     logger: &Logger,
 ) -> Result<BlockResponse<C>, Error> {
@@ -404,7 +420,29 @@ async fn process_firehose_response<C: Blockchain, F: FirehoseMapper<C>>(
         *check_subgraph_continuity = false;
     }
 
-    Ok(BlockResponse::Proceed(event, response.cursor))
+    // Blocks that the filter needs but that Firehose skipped over because
+    // nothing in them matched come before the block we just received
+    let mut events = Vec::new();
+    if let BlockStreamEvent::ProcessBlock(ref block, _) = event {
+        let block_num = block.ptr().number;
+        if endpoint.filters_enabled && block_num > previous_block_num + 1 {
+            let required_blocks = mapper
+                .required_blocks_between(logger, endpoint, previous_block_num, block_num)
+                .await
+                .context("Could not fetch blocks required by the trigger filter")?;
+
+            for required_block in required_blocks {
+                let required_event = mapper
+                    .to_block_stream_event(logger, &required_block)
+                    .await
+                    .context("Mapping required block to BlockStreamEvent failed")?;
+                events.push((required_event, required_block.cursor));
+            }
+        }
+    }
+    events.push((event, response.cursor));
+
+    Ok(BlockResponse::Proceed(events))
 }
 
 impl<C: Blockchain> Stream for FirehoseBlockStream<C> {
@@ -12,6 +12,7 @@ use anyhow::Error;
 use async_trait::async_trait;
 use serde::Deserialize;
 use std::{collections::HashSet, convert::TryFrom, sync::Arc};
+use web3::types::H256;
 
 use super::{
     block_stream::{self, BlockStream, FirehoseCursor},
@@ -36,7 +37,7 @@ pub struct MockBlock {
 
 impl Block for MockBlock {
     fn ptr(&self) -> BlockPtr {
-        todo!()
+        BlockPtr::from((H256::from_low_u64_be(self.number), self.number))
     }
 
     fn parent_ptr(&self) -> Option<BlockPtr> {
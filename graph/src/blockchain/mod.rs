@@ -327,6 +327,18 @@ pub trait DataSource<C: Blockchain>: 'static + Sized + Send + Sync + Clone {
     fn has_declared_calls(&self) -> bool {
         false
     }
+
+    /// Whether the data source handles events emitted by any contract
+    /// rather than by a single address.
+    fn has_wildcard_event_handlers(&self) -> bool {
+        false
+    }
+
+    /// The names of the file data source templates that handlers of this
+    /// data source instantiate without going through the mapping.
+    fn file_source_templates(&self) -> Vec<&str> {
+        vec![]
+    }
 }
 
 #[async_trait]
@@ -388,6 +400,23 @@ pub trait MappingTriggerTrait {
     /// If there is an error when processing this trigger, this will called to add relevant context.
     /// For example an useful return is: `"block #<N> (<hash>), transaction <tx_hash>".
     fn error_context(&self) -> String;
+
+    /// File data sources that the node creates after the handler for this
+    /// trigger has run successfully.
+    fn declared_file_sources(&self) -> &[DeclaredFileSource] {
+        &[]
+    }
+}
+
+/// A file data source declared in the manifest for a handler, created by
+/// the node from a value of the trigger rather than by the mapping.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeclaredFileSource {
+    /// The name of the file data source template
+    pub template: String,
+    /// The source of the file data source, e.g. a CID for `file/ipfs`, or
+    /// `None` if the trigger holds no value that can be turned into one
+    pub source: Option<String>,
 }
 
 /// A callback that is called after the triggers have been decoded.
@@ -400,6 +429,10 @@ pub trait DecoderHook<C: Blockchain> {
         triggers: Vec<RunnableTriggers<'a, C>>,
         metrics: &Arc<SubgraphInstanceMetrics>,
     ) -> Result<Vec<RunnableTriggers<'a, C>>, MappingError>;
+
+    /// Called once all triggers of the block at `block_ptr` have been
+    /// processed
+    fn block_done(&self, _block_ptr: &BlockPtr) {}
 }
 
 /// A decoder hook that does nothing and just returns the triggers that were
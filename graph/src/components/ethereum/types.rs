@@ -122,6 +122,13 @@ pub struct EthereumCall {
     pub block_hash: H256,
     pub transaction_hash: Option<H256>,
     pub transaction_index: u64,
+    /// Position of the call in the transaction's call tree; the top-level
+    /// call has an empty trace address.
+    pub trace_address: Vec<usize>,
+    /// Position of the call among all calls of its transaction, in the
+    /// order in which they were executed; the top-level call is 0. `None`
+    /// if the call was not looked up with the other calls of its block
+    pub trace_ordinal: Option<u32>,
 }
 
 impl EthereumCall {
@@ -158,6 +165,8 @@ impl EthereumCall {
             block_hash: trace.block_hash,
             transaction_hash: trace.transaction_hash,
             transaction_index,
+            trace_address: trace.trace_address.clone(),
+            trace_ordinal: None,
         })
     }
 }
@@ -1,9 +1,9 @@
-use prometheus::Counter;
+use prometheus::{Counter, CounterVec};
 
 use crate::blockchain::block_stream::BlockStreamMetrics;
-use crate::prelude::{Gauge, Histogram, HostMetrics};
+use crate::prelude::{BlockNumber, Gauge, Histogram, HostMetrics};
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use super::stopwatch::StopwatchMetrics;
@@ -19,6 +19,12 @@ pub struct SubgraphInstanceMetrics {
     trigger_processing_duration: Box<Histogram>,
     blocks_processed_secs: Box<Counter>,
     blocks_processed_count: Box<Counter>,
+    trigger_decode_failures: Box<CounterVec>,
+    last_trigger_decode_failure_block: Gauge,
+    /// The block at which each set of labels of `trigger_decode_failures`
+    /// was last counted, so that retrying a block does not count its
+    /// failure again
+    trigger_decode_failure_blocks: Mutex<HashMap<[String; 3], BlockNumber>>,
 }
 
 impl SubgraphInstanceMetrics {
@@ -86,6 +92,25 @@ impl SubgraphInstanceMetrics {
                 labels,
             )
             .expect("failed to create blocks_processed_count counter");
+        let trigger_decode_failures = registry
+            .new_deployment_counter_vec(
+                "deployment_trigger_decode_failures",
+                "Counts the blocks in which a data source failed to decode a trigger for a subgraph deployment",
+                subgraph_hash,
+                vec![
+                    String::from("data_source"),
+                    String::from("handler"),
+                    String::from("kind"),
+                ],
+            )
+            .expect("failed to create `deployment_trigger_decode_failures` counter");
+        let last_trigger_decode_failure_block = registry
+            .new_deployment_gauge(
+                "deployment_last_trigger_decode_failure_block",
+                "The block at which a trigger last failed to decode for a subgraph deployment",
+                subgraph_hash,
+            )
+            .expect("failed to create `deployment_last_trigger_decode_failure_block` gauge");
         Self {
             block_trigger_count,
             block_processing_duration,
@@ -95,6 +120,9 @@ impl SubgraphInstanceMetrics {
             stopwatch,
             blocks_processed_secs,
             blocks_processed_count,
+            trigger_decode_failures,
+            last_trigger_decode_failure_block,
+            trigger_decode_failure_blocks: Mutex::new(HashMap::new()),
         }
     }
 
@@ -109,11 +137,39 @@ impl SubgraphInstanceMetrics {
         }
     }
 
+    /// Record that `data_source` failed to decode a trigger in `block`.
+    /// `handler` is the handler the trigger was meant for if the error is
+    /// deterministic; other errors fail the block before the handler is
+    /// known. Failed blocks are retried, but each failure is only counted
+    /// once per block
+    pub fn observe_trigger_decode_failure(
+        &self,
+        data_source: &str,
+        handler: Option<&str>,
+        block: BlockNumber,
+    ) {
+        let kind = if handler.is_some() {
+            "deterministic"
+        } else {
+            "unknown"
+        };
+        let labels = [data_source, handler.unwrap_or_default(), kind];
+        let mut failure_blocks = self.trigger_decode_failure_blocks.lock().unwrap();
+        if failure_blocks.insert(labels.map(str::to_string), block) != Some(block) {
+            self.trigger_decode_failures
+                .with_label_values(&labels)
+                .inc();
+        }
+        self.last_trigger_decode_failure_block.set(block as f64);
+    }
+
     pub fn unregister(&self, registry: Arc<MetricsRegistry>) {
         registry.unregister(self.block_processing_duration.clone());
         registry.unregister(self.block_trigger_count.clone());
         registry.unregister(self.trigger_processing_duration.clone());
         registry.unregister(self.block_ops_transaction_duration.clone());
+        registry.unregister(self.trigger_decode_failures.clone());
+        registry.unregister(Box::new(self.last_trigger_decode_failure_block.clone()));
     }
 }
 
@@ -154,3 +210,65 @@ pub struct RunnerMetrics {
     /// Sensors to measure the BlockStream metrics
     pub stream: Arc<BlockStreamMetrics>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::prelude::{o, DeploymentHash, Logger};
+    use crate::prometheus::Registry;
+
+    #[test]
+    fn trigger_decode_failures_are_counted_once_per_block() {
+        let logger = Logger::root(slog::Discard, o!());
+        let registry = Arc::new(Registry::new());
+        let metrics_registry = Arc::new(MetricsRegistry::new(logger.clone(), registry.clone()));
+        let deployment = DeploymentHash::new("QmDecodeFailures").unwrap();
+        let stopwatch = StopwatchMetrics::new(
+            logger,
+            deployment.clone(),
+            "test",
+            metrics_registry.clone(),
+            "primary".to_string(),
+        );
+        let metrics =
+            SubgraphInstanceMetrics::new(metrics_registry, deployment.as_str(), stopwatch);
+
+        // Block 5 fails and is retried, then block 7 fails. A trigger for
+        // `handleTransfer` can not be decoded in both blocks
+        metrics.observe_trigger_decode_failure("Token", None, 5);
+        metrics.observe_trigger_decode_failure("Token", None, 5);
+        metrics.observe_trigger_decode_failure("Token", Some("handleTransfer"), 5);
+        metrics.observe_trigger_decode_failure("Token", None, 7);
+        metrics.observe_trigger_decode_failure("Token", Some("handleTransfer"), 7);
+
+        let families = registry.gather();
+        let family = |name: &str| {
+            families
+                .iter()
+                .find(|family| family.get_name() == name)
+                .unwrap_or_else(|| panic!("`{name}` is registered"))
+        };
+        let failures = family("deployment_trigger_decode_failures");
+        let count = |handler: &str, kind: &str| {
+            failures
+                .get_metric()
+                .iter()
+                .find(|metric| {
+                    let label = |name: &str| {
+                        metric
+                            .get_label()
+                            .iter()
+                            .find(|label| label.get_name() == name)
+                            .map_or("", |label| label.get_value())
+                    };
+                    label("handler") == handler && label("kind") == kind
+                })
+                .map(|metric| metric.get_counter().get_value())
+        };
+        assert_eq!(Some(2.0), count("", "unknown"));
+        assert_eq!(Some(2.0), count("handleTransfer", "deterministic"));
+        let last_block = family("deployment_last_trigger_decode_failure_block");
+        assert_eq!(7.0, last_block.get_metric()[0].get_gauge().get_value());
+    }
+}
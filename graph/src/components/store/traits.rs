@@ -578,6 +578,18 @@ pub trait EthereumCallCache: Send + Sync + 'static {
         block: BlockPtr,
         return_value: call::Retval,
     ) -> Result<(), Error>;
+
+    /// Stores many Ethereum calls that were all made at `block` at once.
+    fn set_calls(
+        &self,
+        logger: &Logger,
+        calls: Vec<(call::Request, call::Retval)>,
+        block: BlockPtr,
+    ) -> Result<(), Error>;
+
+    /// Write the calls made at `block` that the cache has held back so far.
+    /// Caches that store calls right away have nothing to do.
+    fn flush(&self, _block: &BlockPtr) {}
 }
 
 pub struct QueryPermit {
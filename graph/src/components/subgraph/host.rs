@@ -9,6 +9,7 @@ use futures01::sync::mpsc;
 use crate::blockchain::BlockTime;
 use crate::components::metrics::gas::GasMetrics;
 use crate::components::store::SubgraphFork;
+use crate::data::store::ethereum::call;
 use crate::data_source::{
     DataSource, DataSourceTemplate, MappingTrigger, TriggerData, TriggerWithHandler,
 };
@@ -23,6 +24,19 @@ pub enum MappingError {
     Unknown(anyhow::Error),
 }
 
+/// The error `DataSource::match_and_decode` returns for a trigger that
+/// matches a handler but can not be decoded for it. Since that fails the
+/// same way on every indexer, the trigger becomes a deterministic error of
+/// the subgraph instead of the block being retried. Only `handler` misses
+/// the trigger; other handlers and triggers of the block still run.
+#[derive(thiserror::Error, Debug)]
+#[error("{error:#}")]
+pub struct DeterministicDecodeError {
+    /// The handler the trigger was meant for
+    pub handler: String,
+    pub error: anyhow::Error,
+}
+
 impl From<anyhow::Error> for MappingError {
     fn from(e: anyhow::Error) -> Self {
         MappingError::Unknown(e)
@@ -114,6 +128,12 @@ pub struct HostMetrics {
     handler_execution_time: Box<HistogramVec>,
     host_fn_execution_time: Box<HistogramVec>,
     eth_call_execution_time: Box<HistogramVec>,
+    eth_call_reverts: Box<CounterVec>,
+    eth_call_sources: Box<CounterVec>,
+    skipped_file_sources: Counter,
+    slow_trigger_decodes: Counter,
+    /// Decoding a trigger that takes longer than this is slow
+    slow_decode_threshold: Duration,
     pub gas_metrics: GasMetrics,
     pub stopwatch: StopwatchMetrics,
 }
@@ -143,6 +163,22 @@ impl HostMetrics {
                 vec![0.1, 0.5, 1.0, 10.0, 100.0],
             )
             .expect("failed to create `deployment_eth_call_execution_time` histogram");
+        let eth_call_reverts = registry
+            .new_deployment_counter_vec(
+                "deployment_eth_call_reverts",
+                "Counts eth_calls that reverted",
+                subgraph,
+                vec![String::from("contract_name"), String::from("method")],
+            )
+            .expect("failed to create `deployment_eth_call_reverts` counter");
+        let eth_call_sources = registry
+            .new_deployment_counter_vec(
+                "deployment_eth_call_source",
+                "Counts eth_calls by whether they were answered from memory, the call cache or an RPC provider",
+                subgraph,
+                vec![String::from("contract_name"), String::from("source")],
+            )
+            .expect("failed to create `deployment_eth_call_source` counter");
 
         let host_fn_execution_time = registry
             .new_deployment_histogram_vec(
@@ -153,15 +189,42 @@ impl HostMetrics {
                 vec![0.025, 0.05, 0.2, 2.0, 8.0, 20.0],
             )
             .expect("failed to create `deployment_host_fn_execution_time` histogram");
+        let skipped_file_sources = registry
+            .new_deployment_counter(
+                "deployment_skipped_file_sources",
+                "Counts file data sources declared by handlers that were skipped because their source was missing or invalid",
+                subgraph,
+            )
+            .expect("failed to create `deployment_skipped_file_sources` counter");
+        let slow_trigger_decodes = registry
+            .new_deployment_counter(
+                "deployment_slow_trigger_decodes",
+                "Counts triggers that took longer than GRAPH_SLOW_DECODE_THRESHOLD_MS to match and decode or to convert for their handler",
+                subgraph,
+            )
+            .expect("failed to create `deployment_slow_trigger_decodes` counter");
         Self {
             handler_execution_time,
             host_fn_execution_time,
             stopwatch,
             gas_metrics,
             eth_call_execution_time,
+            eth_call_reverts,
+            eth_call_sources,
+            skipped_file_sources,
+            slow_trigger_decodes,
+            slow_decode_threshold: ENV_VARS.mappings.slow_decode_threshold,
         }
     }
 
+    /// Use `threshold` instead of `GRAPH_SLOW_DECODE_THRESHOLD_MS` to tell
+    /// whether decoding a trigger was slow
+    #[cfg(debug_assertions)]
+    pub fn with_slow_decode_threshold(mut self, threshold: Duration) -> Self {
+        self.slow_decode_threshold = threshold;
+        self
+    }
+
     pub fn observe_handler_execution_time(&self, duration: f64, handler: &str) {
         self.handler_execution_time
             .with_label_values(&[handler][..])
@@ -185,6 +248,54 @@ impl HostMetrics {
             .observe(duration);
     }
 
+    pub fn inc_eth_call_reverts(&self, contract_name: &str, method: &str) {
+        self.eth_call_reverts
+            .with_label_values(&[contract_name, method][..])
+            .inc();
+    }
+
+    pub fn inc_eth_call_source(&self, contract_name: &str, source: &call::Source) {
+        self.eth_call_sources
+            .with_label_values(&[contract_name, source.as_str()][..])
+            .inc();
+    }
+
+    /// Record the result of an eth_call that was answered from `source`.
+    /// Reverts are counted no matter where the result came from, but only
+    /// calls that were actually made count towards the execution time
+    pub fn observe_eth_call(
+        &self,
+        contract_name: &str,
+        method: &str,
+        source: &call::Source,
+        elapsed: f64,
+        reverted: bool,
+    ) {
+        self.inc_eth_call_source(contract_name, source);
+        if source.observe() {
+            self.observe_eth_call_execution_time(elapsed, contract_name, method);
+        }
+        if reverted {
+            self.inc_eth_call_reverts(contract_name, method);
+        }
+    }
+
+    pub fn inc_skipped_file_sources(&self) {
+        self.skipped_file_sources.inc();
+    }
+
+    /// Return `true` and count the trigger as slow if decoding it took
+    /// longer than the slow decode threshold. Decoding covers matching and
+    /// decoding the trigger for a data source as well as converting it into
+    /// the handler's argument
+    pub fn observe_trigger_decode_time(&self, elapsed: Duration) -> bool {
+        if elapsed <= self.slow_decode_threshold {
+            return false;
+        }
+        self.slow_trigger_decodes.inc();
+        true
+    }
+
     pub fn time_host_fn_execution_region(
         self: Arc<HostMetrics>,
         fn_name: &'static str,
@@ -236,3 +347,43 @@ pub trait RuntimeHostBuilder<C: Blockchain>: Clone + Send + Sync + 'static {
         metrics: Arc<HostMetrics>,
     ) -> Result<mpsc::Sender<Self::Req>, anyhow::Error>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::prometheus::Registry;
+
+    #[test]
+    fn eth_call_reverts_are_counted_for_every_source() {
+        let logger = Logger::root(slog::Discard, o!());
+        let registry = Arc::new(Registry::new());
+        let metrics_registry = Arc::new(MetricsRegistry::new(logger.clone(), registry.clone()));
+        let deployment = DeploymentHash::new("QmEthCallReverts").unwrap();
+        let stopwatch = StopwatchMetrics::new(
+            logger,
+            deployment.clone(),
+            "test",
+            metrics_registry.clone(),
+            "primary".to_string(),
+        );
+        let metrics = HostMetrics::new(
+            metrics_registry.clone(),
+            deployment.as_str(),
+            stopwatch,
+            GasMetrics::new(deployment.clone(), metrics_registry),
+        );
+
+        for source in [call::Source::Memory, call::Source::Store, call::Source::Rpc] {
+            metrics.observe_eth_call("Token", "balanceOf", &source, 0.1, true);
+        }
+        metrics.observe_eth_call("Token", "balanceOf", &call::Source::Rpc, 0.1, false);
+
+        let reverts = registry
+            .gather()
+            .into_iter()
+            .find(|family| family.get_name() == "deployment_eth_call_reverts")
+            .expect("the eth_call reverts counter is registered");
+        assert_eq!(3.0, reverts.get_metric()[0].get_counter().get_value());
+    }
+}
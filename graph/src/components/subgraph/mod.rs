@@ -8,7 +8,9 @@ mod settings;
 
 pub use crate::prelude::Entity;
 
-pub use self::host::{HostMetrics, MappingError, RuntimeHost, RuntimeHostBuilder};
+pub use self::host::{
+    DeterministicDecodeError, HostMetrics, MappingError, RuntimeHost, RuntimeHostBuilder,
+};
 pub use self::instance::{BlockState, InstanceDSTemplate, InstanceDSTemplateInfo};
 pub use self::instance_manager::SubgraphInstanceManager;
 pub use self::proof_of_indexing::{
@@ -11,7 +11,10 @@ use crate::{
 
 use super::{
     store::SubgraphFork,
-    subgraph::{BlockState, MappingError, RuntimeHost, RuntimeHostBuilder, SharedProofOfIndexing},
+    subgraph::{
+        BlockState, DeterministicDecodeError, MappingError, RuntimeHost, RuntimeHostBuilder,
+        SharedProofOfIndexing,
+    },
 };
 
 /// A trigger that is almost ready to run: we have a host to run it on, and
@@ -32,6 +35,8 @@ where
 {
     pub trigger: TriggerData<C>,
     pub hosted_triggers: Vec<HostedTrigger<'a, C>>,
+    /// The handlers that matched the trigger but could not decode it
+    pub decode_errors: Vec<DeterministicDecodeError>,
 }
 
 #[async_trait]
@@ -83,15 +83,19 @@ pub mod call {
         pub fn observe(&self) -> bool {
             matches!(self, Source::Rpc | Source::Store)
         }
+
+        pub fn as_str(&self) -> &'static str {
+            match self {
+                Source::Memory => "memory",
+                Source::Store => "store",
+                Source::Rpc => "rpc",
+            }
+        }
     }
 
     impl std::fmt::Display for Source {
         fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-            match self {
-                Source::Memory => write!(f, "memory"),
-                Source::Store => write!(f, "store"),
-                Source::Rpc => write!(f, "rpc"),
-            }
+            write!(f, "{}", self.as_str())
         }
     }
 
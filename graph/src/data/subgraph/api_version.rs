@@ -21,6 +21,14 @@ pub const API_VERSION_0_0_8: Version = Version::new(0, 0, 8);
 /// Enables new host function `eth_get_balance`
 pub const API_VERSION_0_0_9: Version = Version::new(0, 0, 9);
 
+/// Makes call handler inputs that match a handler's selector but fail to
+/// decode a deterministic error instead of silently skipping the call.
+/// Exposes the trace address of calls to call handlers, and their trace
+/// ordinal to call handlers with `traceOrdinal: true`,
+/// and makes `transactionLogIndex` of events the index of the log within its
+/// transaction. RPC and firehose blocks give the same values for them.
+pub const API_VERSION_0_0_10: Version = Version::new(0, 0, 10);
+
 /// Before this check was introduced, there were already subgraphs in the wild with spec version
 /// 0.0.3, due to confusion with the api version. To avoid breaking those, we accept 0.0.3 though it
 /// doesn't exist.
@@ -54,8 +62,16 @@ pub const SPEC_VERSION_1_1_0: Version = Version::new(1, 1, 0);
 // Enables eth call declarations and indexed arguments(topics) filtering in manifest
 pub const SPEC_VERSION_1_2_0: Version = Version::new(1, 2, 0);
 
+// Enables filtering `call` block handlers by the functions that are called
+// Enables `endBlockHandler`
+// Enables `traceOrdinal` on call handlers
+// Requires the `wildcardEvents` feature for event handlers without a contract address
+// Enables `fileSources` on event handlers
+// Enables `dedupe` on event handlers
+pub const SPEC_VERSION_1_3_0: Version = Version::new(1, 3, 0);
+
 // The latest spec version available
-pub const LATEST_VERSION: &Version = &SPEC_VERSION_1_2_0;
+pub const LATEST_VERSION: &Version = &SPEC_VERSION_1_3_0;
 
 pub const MIN_SPEC_VERSION: Version = Version::new(0, 0, 2);
 
@@ -12,7 +12,7 @@
 
 use crate::{
     blockchain::Blockchain,
-    data::subgraph::SubgraphManifest,
+    data::subgraph::{SubgraphManifest, SPEC_VERSION_1_3_0},
     prelude::{Deserialize, Serialize},
     schema::InputSchema,
 };
@@ -39,6 +39,7 @@ pub enum SubgraphFeature {
     ImmutableEntities,
     #[serde(alias = "nonDeterministicIpfs")]
     IpfsOnEthereumContracts,
+    WildcardEvents,
 }
 
 impl fmt::Display for SubgraphFeature {
@@ -94,6 +95,7 @@ pub fn detect_features<C: Blockchain>(
         detect_grafting(manifest),
         detect_full_text_search(&manifest.schema),
         detect_ipfs_on_ethereum_contracts(manifest)?,
+        detect_wildcard_events(manifest),
     ]
     .into_iter()
     .flatten()
@@ -115,6 +117,20 @@ fn detect_grafting<C: Blockchain>(manifest: &SubgraphManifest<C>) -> Option<Subg
     manifest.graft.as_ref().map(|_| SubgraphFeature::Grafting)
 }
 
+/// Event handlers without a contract address match logs from every
+/// contract on the chain, which is expensive to index. Subgraphs written
+/// before this feature existed keep working without declaring it.
+fn detect_wildcard_events<C: Blockchain>(
+    manifest: &SubgraphManifest<C>,
+) -> Option<SubgraphFeature> {
+    (manifest.spec_version >= SPEC_VERSION_1_3_0
+        && manifest
+            .data_sources
+            .iter()
+            .any(|ds| ds.has_wildcard_event_handlers()))
+    .then_some(SubgraphFeature::WildcardEvents)
+}
+
 fn detect_full_text_search(schema: &InputSchema) -> Option<SubgraphFeature> {
     match schema.get_fulltext_directives() {
         Ok(directives) => (!directives.is_empty()).then_some(SubgraphFeature::FullTextSearch),
@@ -152,13 +168,18 @@ fn detect_ipfs_on_ethereum_contracts<C: Blockchain>(
 mod tests {
     use super::*;
     use SubgraphFeature::*;
-    const VARIANTS: [SubgraphFeature; 4] = [
+    const VARIANTS: [SubgraphFeature; 9] = [
         NonFatalErrors,
         Grafting,
         FullTextSearch,
         IpfsOnEthereumContracts,
+        DeclaredEthCalls,
+        Aggregations,
+        ImmutableEntities,
+        BytesAsIds,
+        WildcardEvents,
     ];
-    const STRING: [&str; 8] = [
+    const STRING: [&str; 9] = [
         "nonFatalErrors",
         "grafting",
         "fullTextSearch",
@@ -167,6 +188,7 @@ mod tests {
         "aggregations",
         "immutableEntities",
         "bytesAsIds",
+        "wildcardEvents",
     ];
 
     #[test]
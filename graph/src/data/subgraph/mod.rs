@@ -704,6 +704,27 @@ impl<C: Blockchain> UnvalidatedSubgraphManifest<C> {
             }));
         }
 
+        // Validate that file sources declared by handlers refer to file data
+        // source templates
+        for ds in &self.0.data_sources {
+            for template in ds.file_source_templates() {
+                let is_file_template = self
+                    .0
+                    .templates
+                    .iter()
+                    .any(|t| t.name() == template && t.as_offchain().is_some());
+                if !is_file_template {
+                    errors.push(SubgraphManifestValidationError::DataSourceValidation(
+                        ds.name().to_owned(),
+                        anyhow!(
+                            "file source template `{}` is not a file data source template",
+                            template
+                        ),
+                    ));
+                }
+            }
+        }
+
         // For API versions newer than 0.0.5, validate that all mappings uses the same api_version
         if let Err(different_api_versions) = self.0.unified_mapping_api_version() {
             errors.push(different_api_versions.into());
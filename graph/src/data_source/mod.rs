@@ -9,7 +9,7 @@ mod tests;
 use crate::{
     blockchain::{
         Block, BlockPtr, BlockTime, Blockchain, DataSource as _, DataSourceTemplate as _,
-        MappingTriggerTrait, TriggerData as _, UnresolvedDataSource as _,
+        DeclaredFileSource, MappingTriggerTrait, TriggerData as _, UnresolvedDataSource as _,
         UnresolvedDataSourceTemplate as _,
     },
     components::{
@@ -193,6 +193,20 @@ impl<C: Blockchain> DataSource<C> {
         }
     }
 
+    pub fn has_wildcard_event_handlers(&self) -> bool {
+        match self {
+            Self::Onchain(ds) => ds.has_wildcard_event_handlers(),
+            Self::Offchain(_) => false,
+        }
+    }
+
+    pub fn file_source_templates(&self) -> Vec<&str> {
+        match self {
+            Self::Onchain(ds) => ds.file_source_templates(),
+            Self::Offchain(_) => vec![],
+        }
+    }
+
     pub fn match_and_decode(
         &self,
         trigger: &TriggerData<C>,
@@ -506,6 +520,13 @@ impl<C: Blockchain> MappingTrigger<C> {
             Self::Offchain(_) => None,
         }
     }
+
+    pub fn declared_file_sources(&self) -> &[DeclaredFileSource] {
+        match self {
+            Self::Onchain(trigger) => trigger.declared_file_sources(),
+            Self::Offchain(_) => &[],
+        }
+    }
 }
 
 macro_rules! clone_data_source {
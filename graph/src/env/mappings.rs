@@ -16,7 +16,7 @@ pub struct EnvVarsMapping {
     /// kilobytes). The default value is 10 megabytes.
     pub entity_cache_size: usize,
     /// Set by the environment variable `GRAPH_MAX_API_VERSION`. The default
-    /// value is `0.0.8`.
+    /// value is `0.0.10`.
     pub max_api_version: Version,
     /// Set by the environment variable `GRAPH_MAPPING_HANDLER_TIMEOUT`
     /// (expressed in seconds). No default is provided.
@@ -62,6 +62,25 @@ pub struct EnvVarsMapping {
     /// eth calls before running triggers; instead eth calls happen when
     /// mappings call `ethereum.call`. Off by default.
     pub disable_declared_calls: bool,
+
+    /// Set by the environment variable `GRAPH_DECODE_PARALLELISM`. The
+    /// number of threads in the pool, shared by all subgraphs, that decodes
+    /// the triggers of blocks with many triggers, and the most of them used
+    /// for a single block. The default value is 1, which decodes on the
+    /// block processing thread.
+    pub decode_parallelism: usize,
+
+    /// Set by the environment variable `GRAPH_DECODE_ERROR_DATA_LIMIT`. The
+    /// maximum number of bytes of undecodable trigger data that are included
+    /// in errors and logs, and of the revert data that is logged for
+    /// reverted calls. The default value is 512.
+    pub decode_error_data_limit: usize,
+
+    /// Set by the environment variable `GRAPH_SLOW_DECODE_THRESHOLD_MS`.
+    /// Matching and decoding a trigger for a data source, or converting it
+    /// for its handler, that takes longer than this is logged as a warning.
+    /// The default value is 1000ms.
+    pub slow_decode_threshold: Duration,
 }
 
 // This does not print any values avoid accidentally leaking any sensitive env vars
@@ -89,6 +108,9 @@ impl From<InnerMappingHandlers> for EnvVarsMapping {
             ipfs_request_limit: x.ipfs_request_limit,
             allow_non_deterministic_ipfs: x.allow_non_deterministic_ipfs.0,
             disable_declared_calls: x.disable_declared_calls.0,
+            decode_parallelism: x.decode_parallelism.max(1),
+            decode_error_data_limit: x.decode_error_data_limit,
+            slow_decode_threshold: Duration::from_millis(x.slow_decode_threshold_in_ms),
         }
     }
 }
@@ -99,7 +121,7 @@ pub struct InnerMappingHandlers {
     entity_cache_dead_weight: EnvVarBoolean,
     #[envconfig(from = "GRAPH_ENTITY_CACHE_SIZE", default = "10000")]
     entity_cache_size_in_kb: usize,
-    #[envconfig(from = "GRAPH_MAX_API_VERSION", default = "0.0.9")]
+    #[envconfig(from = "GRAPH_MAX_API_VERSION", default = "0.0.10")]
     max_api_version: Version,
     #[envconfig(from = "GRAPH_MAPPING_HANDLER_TIMEOUT")]
     mapping_handler_timeout_in_secs: Option<u64>,
@@ -123,4 +145,10 @@ pub struct InnerMappingHandlers {
     allow_non_deterministic_ipfs: EnvVarBoolean,
     #[envconfig(from = "GRAPH_DISABLE_DECLARED_CALLS", default = "false")]
     disable_declared_calls: EnvVarBoolean,
+    #[envconfig(from = "GRAPH_DECODE_PARALLELISM", default = "1")]
+    decode_parallelism: usize,
+    #[envconfig(from = "GRAPH_DECODE_ERROR_DATA_LIMIT", default = "512")]
+    decode_error_data_limit: usize,
+    #[envconfig(from = "GRAPH_SLOW_DECODE_THRESHOLD_MS", default = "1000")]
+    slow_decode_threshold_in_ms: u64,
 }
@@ -528,6 +528,21 @@ impl FirehoseEndpoint {
         logger: &Logger,
         number: BlockNumber,
     ) -> Result<BlockPtr, anyhow::Error>
+    where
+        M: prost::Message + BlockchainBlock + Default + 'static,
+    {
+        let response = self.block_response_for_number::<M>(logger, number).await?;
+
+        Ok(decode_firehose_block::<M>(&response)?.ptr())
+    }
+
+    /// Returns the stream response, including its cursor, for the block
+    /// with the given number on the longuest chain according to Firehose
+    pub async fn block_response_for_number<M>(
+        &self,
+        logger: &Logger,
+        number: BlockNumber,
+    ) -> Result<firehose::Response, anyhow::Error>
     where
         M: prost::Message + BlockchainBlock + Default + 'static,
     {
@@ -565,7 +580,7 @@ impl FirehoseEndpoint {
         debug!(logger, "Retrieving block(s) from firehose";
                "provider" => self.provider.as_str());
 
-        let mut latest_received_block: Option<BlockPtr> = None;
+        let mut latest_received_block: Option<(BlockPtr, firehose::Response)> = None;
         while let Some(message) = block_stream.next().await {
             match message {
                 Ok(v) => {
@@ -573,9 +588,9 @@ impl FirehoseEndpoint {
 
                     match latest_received_block {
                         None => {
-                            latest_received_block = Some(block);
+                            latest_received_block = Some((block, v));
                         }
-                        Some(ref actual_ptr) => {
+                        Some((ref actual_ptr, _)) => {
                             // We want to receive all events related to a specific block number,
                             // however, in some circumstances, it seems Firehose would not stop sending
                             // blocks (`start_block_num: 0 and stop_block_num: 0` on NEAR seems to trigger
@@ -588,7 +603,7 @@ impl FirehoseEndpoint {
                                 break;
                             }
 
-                            latest_received_block = Some(block);
+                            latest_received_block = Some((block, v));
                         }
                     }
                 }
@@ -597,7 +612,7 @@ impl FirehoseEndpoint {
         }
 
         match latest_received_block {
-            Some(block_ptr) => Ok(block_ptr),
+            Some((_, response)) => Ok(response),
             None => Err(anyhow::format_err!(
                 "Firehose should have returned at least one block for request"
             )),
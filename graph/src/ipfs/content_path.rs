@@ -1,4 +1,5 @@
 use anyhow::anyhow;
+use cid::multihash::Multihash;
 use cid::Cid;
 
 use crate::ipfs::IpfsError;
@@ -49,6 +50,16 @@ impl ContentPath {
         })
     }
 
+    /// Creates a new [ContentPath] for the CIDv0 of a sha2-256 digest.
+    pub fn from_sha256_digest(digest: &[u8; 32]) -> Self {
+        const SHA2_256: u64 = 0x12;
+
+        let hash = Multihash::wrap(SHA2_256, digest).expect("a 32 byte digest fits a multihash");
+        let cid = Cid::new_v0(hash).expect("a sha2-256 multihash is a valid CIDv0");
+
+        Self { cid, path: None }
+    }
+
     pub fn cid(&self) -> &Cid {
         &self.cid
     }
@@ -142,6 +153,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn creates_a_cid_v0_from_a_sha256_digest() {
+        let digest =
+            hex_literal::hex!("59948439065f29619ef41280cbb932be52c56d99c5966b65e0111239f098bbef");
+
+        let path = ContentPath::from_sha256_digest(&digest);
+
+        assert_eq!(path.to_string(), CID_V0);
+        assert_eq!(path, ContentPath::new(CID_V0).unwrap());
+    }
+
     #[test]
     fn fails_on_a_leading_slash_followed_by_a_valid_cid() {
         let err = ContentPath::new(format!("/{CID_V0}")).unwrap_err();
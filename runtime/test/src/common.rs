@@ -1,11 +1,16 @@
 use ethabi::Contract;
 use graph::blockchain::BlockTime;
+use graph::bytes::Bytes;
 use graph::components::store::DeploymentLocator;
 use graph::data::subgraph::*;
 use graph::data_source;
+use graph::data_source::offchain::{self, OffchainDataSourceKind};
 use graph::env::EnvVars;
-use graph::ipfs::IpfsRpcClient;
-use graph::ipfs::ServerAddress;
+use graph::futures03::stream::{self, BoxStream, StreamExt};
+use graph::ipfs::{
+    CanProvide, Cat, CatStream, ContentPath, GetBlock, IpfsClient, IpfsError, IpfsResult,
+    IpfsRpcClient, ServerAddress,
+};
 use graph::log;
 use graph::prelude::*;
 use graph_chain_ethereum::{
@@ -14,6 +19,7 @@ use graph_chain_ethereum::{
 use graph_runtime_wasm::host_exports::DataSourceDetails;
 use graph_runtime_wasm::{HostExports, MappingContext};
 use semver::Version;
+use std::collections::HashMap;
 use std::env;
 use std::str::FromStr;
 use web3::types::Address;
@@ -25,13 +31,98 @@ lazy_static! {
     };
 }
 
+/// An IPFS client that serves files from memory, so tests don't need an
+/// IPFS node
+#[derive(Default)]
+pub struct MockIpfsClient {
+    files: HashMap<String, Bytes>,
+}
+
+impl MockIpfsClient {
+    pub fn with_file(mut self, path: &str, content: &str) -> Self {
+        self.files
+            .insert(path.to_owned(), Bytes::from(content.to_owned()));
+        self
+    }
+
+    fn file(&self, path: &ContentPath) -> IpfsResult<Bytes> {
+        self.files
+            .get(&path.to_string())
+            .cloned()
+            .ok_or_else(|| IpfsError::ContentNotAvailable {
+                path: path.clone(),
+                reason: anyhow!("not in the mock IPFS client"),
+            })
+    }
+}
+
+#[async_trait]
+impl CanProvide for MockIpfsClient {
+    async fn can_provide(&self, path: &ContentPath, _: Option<Duration>) -> IpfsResult<bool> {
+        Ok(self.files.contains_key(&path.to_string()))
+    }
+}
+
+#[async_trait]
+impl CatStream for MockIpfsClient {
+    async fn cat_stream(
+        &self,
+        path: &ContentPath,
+        _: Option<Duration>,
+    ) -> IpfsResult<BoxStream<'static, IpfsResult<Bytes>>> {
+        let file = self.file(path)?;
+        Ok(stream::once(async move { Ok(file) }).boxed())
+    }
+}
+
+#[async_trait]
+impl Cat for MockIpfsClient {
+    async fn cat(
+        &self,
+        path: &ContentPath,
+        _max_size: usize,
+        _: Option<Duration>,
+    ) -> IpfsResult<Bytes> {
+        self.file(path)
+    }
+}
+
+#[async_trait]
+impl GetBlock for MockIpfsClient {
+    async fn get_block(&self, path: &ContentPath, _: Option<Duration>) -> IpfsResult<Bytes> {
+        self.file(path)
+    }
+}
+
 fn mock_host_exports(
     subgraph_id: DeploymentHash,
     data_source: DataSource,
     store: Arc<impl SubgraphStore>,
     api_version: Version,
 ) -> HostExports {
-    let templates = vec![data_source::DataSourceTemplate::Onchain::<Chain>(
+    let ipfs_client = IpfsRpcClient::new_unchecked(ServerAddress::local_rpc_api(), &LOGGER)
+        .unwrap()
+        .into_boxed();
+
+    mock_host_exports_with(
+        subgraph_id,
+        data_source,
+        vec![],
+        store,
+        api_version,
+        ipfs_client.into(),
+    )
+}
+
+fn mock_host_exports_with(
+    subgraph_id: DeploymentHash,
+    data_source: DataSource,
+    extra_templates: Vec<data_source::DataSourceTemplate<Chain>>,
+    store: Arc<impl SubgraphStore>,
+    api_version: Version,
+    ipfs_client: Arc<dyn IpfsClient>,
+) -> HostExports {
+    let mut templates = vec![data_source::DataSourceTemplate::Onchain::<Chain>(
         DataSourceTemplate {
             kind: String::from("ethereum/contract"),
             name: String::from("example template"),
@@ -46,9 +137,10 @@ fn mock_host_exports(
                 language: String::from("wasm/assemblyscript"),
                 entities: vec![],
                 abis: vec![],
-                event_handlers: vec![],
+                event_handlers: Default::default(),
                 call_handlers: vec![],
                 block_handlers: vec![],
+                end_block_handler: None,
                 link: Link {
                     link: "link".to_owned(),
                 },
@@ -56,6 +148,7 @@ fn mock_host_exports(
             },
         },
     )];
+    templates.extend(extra_templates);
 
     let network = data_source.network.clone().unwrap();
     let ens_lookup = store.ens_lookup();
@@ -65,26 +158,19 @@ fn mock_host_exports(
         Arc::new(templates.iter().map(|t| t.into()).collect()),
     );
 
-    let ipfs_client = IpfsRpcClient::new_unchecked(ServerAddress::local_rpc_api(), &LOGGER)
-        .unwrap()
-        .into_boxed();
-
     HostExports::new(
         subgraph_id,
         network,
         ds_details,
-        Arc::new(IpfsResolver::new(
-            ipfs_client.into(),
-            Arc::new(EnvVars::default()),
-        )),
+        Arc::new(IpfsResolver::new(ipfs_client, Arc::new(EnvVars::default()))),
         ens_lookup,
     )
 }
 
 fn mock_abi() -> MappingABI {
-    MappingABI {
-        name: "mock_abi".to_string(),
-        contract: Contract::load(
+    MappingABI::new(
+        "mock_abi".to_string(),
+        Contract::load(
             r#"[
             {
                 "inputs": [
@@ -99,7 +185,7 @@ fn mock_abi() -> MappingABI {
             .as_bytes(),
         )
         .unwrap(),
-    }
+    )
 }
 
 pub fn mock_context(
@@ -107,6 +193,57 @@ pub fn mock_context(
     data_source: DataSource,
     store: Arc<impl SubgraphStore>,
     api_version: Version,
+) -> MappingContext {
+    let host_exports = mock_host_exports(
+        deployment.hash.clone(),
+        data_source,
+        store.clone(),
+        api_version,
+    );
+    mock_context_with(deployment, host_exports, store)
+}
+
+/// A context whose host exports also have a `file/ipfs` template called
+/// `File` and that resolve IPFS links with `ipfs_client`
+pub fn mock_file_template_context(
+    deployment: DeploymentLocator,
+    data_source: DataSource,
+    store: Arc<impl SubgraphStore>,
+    api_version: Version,
+    ipfs_client: MockIpfsClient,
+) -> MappingContext {
+    let file_template = data_source::DataSourceTemplate::Offchain(offchain::DataSourceTemplate {
+        kind: OffchainDataSourceKind::Ipfs,
+        network: None,
+        name: String::from("File"),
+        manifest_idx: 1,
+        mapping: offchain::Mapping {
+            language: String::from("wasm/assemblyscript"),
+            api_version: api_version.clone(),
+            entities: vec![],
+            handler: String::from("handleFile"),
+            runtime: Arc::new(vec![]),
+            link: Link {
+                link: "link".to_owned(),
+            },
+        },
+    });
+
+    let host_exports = mock_host_exports_with(
+        deployment.hash.clone(),
+        data_source,
+        vec![file_template],
+        store.clone(),
+        api_version,
+        Arc::new(ipfs_client),
+    );
+    mock_context_with(deployment, host_exports, store)
+}
+
+fn mock_context_with(
+    deployment: DeploymentLocator,
+    host_exports: HostExports,
+    store: Arc<impl SubgraphStore>,
 ) -> MappingContext {
     MappingContext {
         logger: Logger::root(slog::Discard, o!()),
@@ -115,12 +252,7 @@ pub fn mock_context(
             number: 0,
         },
         timestamp: BlockTime::NONE,
-        host_exports: Arc::new(mock_host_exports(
-            deployment.hash.clone(),
-            data_source,
-            store.clone(),
-            api_version,
-        )),
+        host_exports: Arc::new(host_exports),
         state: BlockState::new(
             graph::futures03::executor::block_on(store.writable(
                 LOGGER.clone(),
@@ -155,9 +287,10 @@ pub fn mock_data_source(path: &str, api_version: Version) -> DataSource {
             language: String::from("wasm/assemblyscript"),
             entities: vec![],
             abis: vec![],
-            event_handlers: vec![],
+            event_handlers: Default::default(),
             call_handlers: vec![],
             block_handlers: vec![],
+            end_block_handler: None,
             link: Link {
                 link: "link".to_owned(),
             },
@@ -1,4 +1,4 @@
-use graph::blockchain::BlockTime;
+use graph::blockchain::{BlockTime, DeclaredFileSource};
 use graph::components::metrics::gas::GasMetrics;
 use graph::components::store::*;
 use graph::data::store::{scalar, Id, IdType};
@@ -23,7 +23,7 @@ use test_store::{LOGGER, STORE};
 use wasmtime::{AsContext, AsContextMut};
 use web3::types::H160;
 
-use crate::common::{mock_context, mock_data_source};
+use crate::common::{mock_context, mock_data_source, mock_file_template_context, MockIpfsClient};
 
 mod abi;
 
@@ -955,6 +955,158 @@ async fn data_source_create_v0_0_5() {
     test_data_source_create(API_VERSION_0_0_5, 101450079).await;
 }
 
+#[tokio::test]
+async fn declared_file_sources_create() {
+    let api_version = API_VERSION_0_0_5;
+    let cid = "QmVkvoPGi9jvvuxsHDVJDgzPEzagBaWSZRYoRDzU244HjZ";
+    let ds = mock_data_source(
+        &wasm_file_path("data_source_create.wasm", api_version.clone()),
+        api_version.clone(),
+    );
+
+    let store = STORE.clone();
+    let deployment = DeploymentHash::new("declaredFileSourcesCreate").unwrap();
+    let deployment =
+        test_store::create_test_subgraph(&deployment, "type User @entity { id: ID! }").await;
+    let ipfs_client = MockIpfsClient::default().with_file(cid, "{}");
+    let mut ctx = mock_file_template_context(
+        deployment.clone(),
+        ds,
+        store.subgraph_store(),
+        api_version,
+        ipfs_client,
+    );
+    let host_exports = host_exports::test_support::HostExports::new(&ctx);
+
+    let registry = Arc::new(graph::prometheus::Registry::new());
+    let metrics_registry = Arc::new(MetricsRegistry::new(ctx.logger.clone(), registry.clone()));
+    let stopwatch = StopwatchMetrics::new(
+        ctx.logger.clone(),
+        deployment.hash.clone(),
+        "test",
+        metrics_registry.clone(),
+        "test_shard".to_string(),
+    );
+    let gas_metrics = GasMetrics::new(deployment.hash.clone(), metrics_registry.clone());
+    let host_metrics = HostMetrics::new(
+        metrics_registry,
+        deployment.hash.as_str(),
+        stopwatch,
+        gas_metrics,
+    );
+
+    let file_source = |template: &str, source: Option<&str>| DeclaredFileSource {
+        template: template.to_string(),
+        source: source.map(str::to_string),
+    };
+    let file_sources = vec![
+        file_source("File", Some(cid)),
+        // Not a CID
+        file_source("File", Some("not a cid")),
+        // The parameter had no usable value
+        file_source("File", None),
+        // Not a file data source template
+        file_source("example template", Some(cid)),
+    ];
+
+    ctx.state.enter_handler();
+    host_exports.declared_file_sources_create(
+        &ctx.logger,
+        &mut ctx.state,
+        &file_sources,
+        1,
+        &host_metrics,
+    );
+    ctx.state.exit_handler();
+
+    let created = ctx.state.drain_created_data_sources();
+    assert_eq!(1, created.len());
+    assert_eq!("File", created[0].template.name());
+    assert_eq!(vec![cid.to_string()], created[0].params);
+
+    let skipped = registry
+        .gather()
+        .into_iter()
+        .find(|family| family.get_name() == "deployment_skipped_file_sources")
+        .expect("the skipped file sources counter is registered");
+    assert_eq!(3.0, skipped.get_metric()[0].get_counter().get_value());
+}
+
+#[tokio::test]
+async fn declared_file_sources_are_discarded_when_the_handler_fails() {
+    let api_version = API_VERSION_0_0_5;
+    let cid = "QmVkvoPGi9jvvuxsHDVJDgzPEzagBaWSZRYoRDzU244HjZ";
+    let logger = Logger::root(slog::Discard, o!());
+    let store = STORE.clone();
+    let deployment = DeploymentHash::new("declaredFileSourcesHandlerFails").unwrap();
+    let deployment = test_store::create_test_subgraph(
+        &deployment,
+        "type User @entity { id: ID!, count: BigInt }",
+    )
+    .await;
+    let file_sources = vec![DeclaredFileSource {
+        template: "File".to_string(),
+        source: Some(cid.to_string()),
+    }];
+
+    // Handle a trigger that declares `file_sources` with `bigIntWithLength`,
+    // which traps when the length is too big
+    let handle = |len: u32| {
+        let ds = mock_data_source(
+            &wasm_file_path("big_int_size_limit.wasm", api_version.clone()),
+            api_version.clone(),
+        );
+        let valid_module =
+            Arc::new(ValidModule::new(&logger, ds.mapping.runtime.as_ref(), None).unwrap());
+        let ctx = mock_file_template_context(
+            deployment.clone(),
+            ds,
+            store.subgraph_store(),
+            api_version.clone(),
+            MockIpfsClient::default(),
+        );
+        let metrics_registry = Arc::new(MetricsRegistry::mock());
+        let stopwatch = StopwatchMetrics::new(
+            logger.clone(),
+            deployment.hash.clone(),
+            "test",
+            metrics_registry.clone(),
+            "test_shard".to_string(),
+        );
+        let host_metrics = Arc::new(HostMetrics::new(
+            metrics_registry,
+            deployment.hash.as_str(),
+            stopwatch,
+            GasMetrics::mock(),
+        ));
+        let instance = WasmInstance::from_valid_module_with_ctx(
+            valid_module,
+            ctx,
+            host_metrics,
+            ExperimentalFeatures {
+                allow_non_deterministic_ipfs: true,
+            },
+        )
+        .unwrap();
+        let (mut state, _) = instance
+            .handle_with_file_sources("bigIntWithLength", len, &file_sources)
+            .unwrap();
+        (
+            state.deterministic_errors.len(),
+            state.drain_created_data_sources(),
+        )
+    };
+
+    let (errors, created) = handle(BigInt::MAX_BITS / 8);
+    assert_eq!(0, errors);
+    assert_eq!(1, created.len());
+    assert_eq!(vec![cid.to_string()], created[0].params);
+
+    let (errors, created) = handle(BigInt::MAX_BITS / 8 + 1);
+    assert_eq!(1, errors);
+    assert!(created.is_empty());
+}
+
 async fn test_ens_name_by_hash(api_version: Version) {
     let mut module = test_module(
         "EnsNameByHash",
@@ -14,6 +14,7 @@ use web3::types::H160;
 
 use graph::blockchain::BlockTime;
 use graph::blockchain::Blockchain;
+use graph::blockchain::DeclaredFileSource;
 use graph::components::store::{EnsLookup, GetScope, LoadRelatedRequest};
 use graph::components::subgraph::{
     InstanceDSTemplate, PoICausalityRegion, ProofOfIndexingEvent, SharedProofOfIndexing,
@@ -1043,6 +1044,85 @@ impl HostExports {
         Ok(())
     }
 
+    /// Create the file data sources that the manifest declares for the
+    /// handler of a trigger; must be called while the handler is running.
+    /// Their sources come from chain data, so sources that are missing or
+    /// can not be used with their template are skipped with a warning
+    /// rather than failing the subgraph.
+    pub(crate) fn declared_file_sources_create(
+        &self,
+        logger: &Logger,
+        state: &mut BlockState,
+        file_sources: &[DeclaredFileSource],
+        creation_block: BlockNumber,
+        metrics: &HostMetrics,
+    ) {
+        for file_source in file_sources {
+            let source = match &file_source.source {
+                Some(source) => source,
+                None => {
+                    warn!(
+                        logger,
+                        "Skipping file data source with an unusable parameter value";
+                        "template" => &file_source.template,
+                    );
+                    metrics.inc_skipped_file_sources();
+                    continue;
+                }
+            };
+            let template = self
+                .data_source
+                .templates
+                .iter()
+                .find(|template| template.name() == file_source.template);
+            let template = match template {
+                Some(template @ InstanceDSTemplate::Offchain(offchain)) => {
+                    match offchain
+                        .kind
+                        .try_parse_source(source.as_bytes().to_vec().into())
+                    {
+                        Ok(_) => template.clone(),
+                        Err(e) => {
+                            warn!(
+                                logger,
+                                "Skipping file data source with an invalid source";
+                                "template" => &file_source.template,
+                                "source" => source,
+                                "error" => e.to_string(),
+                            );
+                            metrics.inc_skipped_file_sources();
+                            continue;
+                        }
+                    }
+                }
+                _ => {
+                    warn!(
+                        logger,
+                        "Skipping file data source without a file data source template";
+                        "template" => &file_source.template,
+                        "source" => source,
+                    );
+                    metrics.inc_skipped_file_sources();
+                    continue;
+                }
+            };
+
+            info!(
+                logger,
+                "Create declared file data source";
+                "name" => &file_source.template,
+                "source" => source,
+            );
+
+            state.push_created_data_source(InstanceDSTemplateInfo {
+                template,
+                params: vec![source.clone()],
+                context: None,
+                creation_block,
+            });
+        }
+    }
+
     pub(crate) fn ens_name_by_hash(
         &self,
         hash: &str,
@@ -1265,10 +1345,10 @@ pub mod test_support {
     use std::{collections::HashMap, sync::Arc};
 
     use graph::{
-        blockchain::BlockTime,
+        blockchain::{BlockTime, DeclaredFileSource},
         components::{
             store::{BlockNumber, GetScope},
-            subgraph::SharedProofOfIndexing,
+            subgraph::{HostMetrics, SharedProofOfIndexing},
         },
         data::value::Word,
         prelude::{BlockState, Entity, StopwatchMetrics, Value},
@@ -1327,6 +1407,23 @@ pub mod test_support {
             self.host_exports
                 .store_get(state, entity_type, entity_id, gas, GetScope::Store)
         }
+
+        pub fn declared_file_sources_create(
+            &self,
+            logger: &Logger,
+            state: &mut BlockState,
+            file_sources: &[DeclaredFileSource],
+            creation_block: BlockNumber,
+            metrics: &HostMetrics,
+        ) {
+            self.host_exports.declared_file_sources_create(
+                logger,
+                state,
+                file_sources,
+                creation_block,
+                metrics,
+            )
+        }
     }
 }
 #[test]
@@ -7,7 +7,7 @@ use graph::slog::SendSyncRefUnwindSafeKV;
 use semver::Version;
 use wasmtime::{AsContextMut, Linker, Store, Trap};
 
-use graph::blockchain::{Blockchain, HostFnCtx};
+use graph::blockchain::{Blockchain, DeclaredFileSource, HostFnCtx};
 use graph::data::store;
 use graph::data::subgraph::schema::SubgraphError;
 use graph::data_source::{MappingTrigger, TriggerWithHandler};
@@ -117,7 +117,7 @@ impl WasmInstance {
 
         let obj = AscPtr::alloc_obj(obj, &mut ctx, &gas)?;
 
-        self.invoke_handler(handler_name, obj, Arc::new(o!()), None)
+        self.invoke_handler(handler_name, obj, Arc::new(o!()), None, &[])
     }
 
     pub(crate) fn handle_trigger<C: Blockchain>(
@@ -131,10 +131,46 @@ impl WasmInstance {
         let gas = self.gas.clone();
         let logging_extras = trigger.logging_extras().cheap_clone();
         let error_context = trigger.trigger.error_context();
+        let file_sources = trigger.trigger.declared_file_sources().to_vec();
+        let start = Instant::now();
         let mut ctx = self.instance_ctx();
         let asc_trigger = trigger.to_asc_ptr(&mut ctx, &gas)?;
 
-        self.invoke_handler(&handler_name, asc_trigger, logging_extras, error_context)
+        let elapsed = start.elapsed();
+        let data = self.store.data();
+        if data.host_metrics.observe_trigger_decode_time(elapsed) {
+            warn!(data.ctx.logger, "Converting a trigger for its handler was slow";
+                "handler" => &handler_name,
+                "trigger" => &error_context,
+                "elapsed_ms" => elapsed.as_millis(),
+            );
+        }
+
+        self.invoke_handler(
+            &handler_name,
+            asc_trigger,
+            logging_extras,
+            error_context,
+            &file_sources,
+        )
+    }
+
+    /// Invokes `handler` with `arg` like a trigger that declares
+    /// `file_sources` is handled
+    #[cfg(debug_assertions)]
+    pub fn handle_with_file_sources(
+        self,
+        handler: &str,
+        arg: u32,
+        file_sources: &[DeclaredFileSource],
+    ) -> Result<(BlockState, Gas), MappingError> {
+        self.invoke_handler::<()>(
+            handler,
+            AscPtr::new(arg),
+            Arc::new(o!()),
+            None,
+            file_sources,
+        )
     }
 
     pub fn take_ctx(self) -> WasmInstanceData {
@@ -163,6 +199,7 @@ impl WasmInstance {
         arg: AscPtr<T>,
         logging_extras: Arc<dyn SendSyncRefUnwindSafeKV>,
         error_context: Option<String>,
+        file_sources: &[DeclaredFileSource],
     ) -> Result<(BlockState, Gas), MappingError> {
         let func = self
             .instance
@@ -250,7 +287,19 @@ impl WasmInstance {
                 .state
                 .exit_handler_and_discard_changes_due_to_error(subgraph_error);
         } else {
-            self.instance_ctx().as_mut().ctx.state.exit_handler();
+            // The file data sources the manifest declares for the trigger
+            // are created by the handler, and discarded with its other
+            // changes if it fails
+            let data = self.store.data_mut();
+            let ctx = &mut data.ctx;
+            ctx.host_exports.declared_file_sources_create(
+                &ctx.logger,
+                &mut ctx.state,
+                file_sources,
+                ctx.block_ptr.number,
+                &data.host_metrics,
+            );
+            ctx.state.exit_handler();
         }
 
         let gas = self.gas.get();
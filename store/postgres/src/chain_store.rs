@@ -1378,10 +1378,9 @@ mod data {
             block_number: i32,
             return_value: &[u8],
         ) -> Result<(), Error> {
-            let result = match self {
+            match self {
                 Storage::Shared => {
                     use public::eth_call_cache as cache;
-                    use public::eth_call_meta as meta;
 
                     insert_into(cache::table)
                         .values((
@@ -1392,6 +1391,74 @@ mod data {
                         ))
                         .on_conflict_do_nothing()
                         .execute(conn)?;
+                }
+                Storage::Private(Schema { call_cache, .. }) => {
+                    let query = format!(
+                        "insert into {}(id, contract_address, block_number, return_value) \
+                         values ($1, $2, $3, $4) on conflict do nothing",
+                        call_cache.qname
+                    );
+                    sql_query(query)
+                        .bind::<Bytea, _>(id)
+                        .bind::<Bytea, _>(contract_address)
+                        .bind::<Integer, _>(block_number)
+                        .bind::<Bytea, _>(return_value)
+                        .execute(conn)?;
+                }
+            }
+            self.update_call_meta(conn, contract_address)
+        }
+
+        /// Insert the `(id, contract_address, return_value)` of many calls
+        /// made at `block_number` with one statement. Calls that are
+        /// already in the cache, for example because another index node
+        /// stored them first, are left alone just like in `set_call`
+        pub(super) fn set_calls(
+            &self,
+            conn: &mut PgConnection,
+            calls: &[(&[u8], &[u8], &[u8])],
+            block_number: i32,
+        ) -> Result<(), Error> {
+            let ids: Vec<&[u8]> = calls.iter().map(|(id, _, _)| *id).collect();
+            let addresses: Vec<&[u8]> = calls.iter().map(|(_, address, _)| *address).collect();
+            let return_values: Vec<&[u8]> = calls.iter().map(|(_, _, value)| *value).collect();
+
+            let call_cache = match self {
+                Storage::Shared => ETHEREUM_CALL_CACHE_TABLE_NAME,
+                Storage::Private(Schema { call_cache, .. }) => call_cache.qname.as_str(),
+            };
+            let query = format!(
+                "insert into {call_cache}(id, contract_address, block_number, return_value) \
+                 select c.id, c.contract_address, $3, c.return_value \
+                   from unnest($1, $2, $4) as c(id, contract_address, return_value) \
+                 on conflict do nothing"
+            );
+            sql_query(query)
+                .bind::<Array<Bytea>, _>(ids)
+                .bind::<Array<Bytea>, _>(addresses.clone())
+                .bind::<Integer, _>(block_number)
+                .bind::<Array<Bytea>, _>(return_values)
+                .execute(conn)?;
+
+            let mut addresses = addresses;
+            addresses.sort_unstable();
+            addresses.dedup();
+            for contract_address in addresses {
+                self.update_call_meta(conn, contract_address)?;
+            }
+            Ok(())
+        }
+
+        /// Mark `contract_address` as accessed today after storing a call
+        /// for it
+        fn update_call_meta(
+            &self,
+            conn: &mut PgConnection,
+            contract_address: &[u8],
+        ) -> Result<(), Error> {
+            let result = match self {
+                Storage::Shared => {
+                    use public::eth_call_meta as meta;
 
                     // See comment in the Private branch for why the
                     // raciness of this check is ok
@@ -1419,23 +1486,7 @@ mod data {
                         Ok(0)
                     }
                 }
-                Storage::Private(Schema {
-                    call_cache,
-                    call_meta,
-                    ..
-                }) => {
-                    let query = format!(
-                        "insert into {}(id, contract_address, block_number, return_value) \
-                         values ($1, $2, $3, $4) on conflict do nothing",
-                        call_cache.qname
-                    );
-                    sql_query(query)
-                        .bind::<Bytea, _>(id)
-                        .bind::<Bytea, _>(contract_address)
-                        .bind::<Integer, _>(block_number)
-                        .bind::<Bytea, _>(return_value)
-                        .execute(conn)?;
-
+                Storage::Private(Schema { call_meta, .. }) => {
                     // Check whether we need to update `call_meta`. The
                     // check is racy, since an update can happen between the
                     // check and the insert below, but that's fine. We can
@@ -2627,6 +2678,36 @@ impl EthereumCallCache for ChainStore {
             )
         })
     }
+
+    fn set_calls(
+        &self,
+        _: &Logger,
+        calls: Vec<(call::Request, call::Retval)>,
+        block: BlockPtr,
+    ) -> Result<(), Error> {
+        // Unsuccessful calls are not cached, see `set_call`
+        let calls: Vec<_> = calls
+            .into_iter()
+            .filter_map(|(call, return_value)| match return_value {
+                call::Retval::Value(return_value) => {
+                    Some((contract_call_id(&call, &block), call.address, return_value))
+                }
+                call::Retval::Null => None,
+            })
+            .collect();
+        if calls.is_empty() {
+            return Ok(());
+        }
+
+        let rows: Vec<(&[u8], &[u8], &[u8])> = calls
+            .iter()
+            .map(|(id, address, return_value)| {
+                (id.as_slice(), address.as_ref(), return_value.as_ref())
+            })
+            .collect();
+        let conn = &mut *self.get_conn()?;
+        conn.transaction(|conn| self.storage.set_calls(conn, &rows, block.number))
+    }
 }
 
 /// The id is the hashed encoded_call + contract_address + block hash to uniquely identify the call.
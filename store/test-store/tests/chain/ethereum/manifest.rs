@@ -8,10 +8,11 @@ use graph::blockchain::DataSource;
 use graph::components::store::BLOCK_NUMBER_MAX;
 use graph::data::store::scalar::Bytes;
 use graph::data::store::Value;
+use graph::data::subgraph::features::validate_subgraph_features;
 use graph::data::subgraph::schema::SubgraphError;
 use graph::data::subgraph::{
     Prune, LATEST_VERSION, SPEC_VERSION_0_0_4, SPEC_VERSION_0_0_7, SPEC_VERSION_0_0_8,
-    SPEC_VERSION_0_0_9, SPEC_VERSION_1_0_0, SPEC_VERSION_1_2_0,
+    SPEC_VERSION_0_0_9, SPEC_VERSION_1_0_0, SPEC_VERSION_1_2_0, SPEC_VERSION_1_3_0,
 };
 use graph::data_source::offchain::OffchainDataSourceKind;
 use graph::data_source::DataSourceTemplate;
@@ -890,11 +891,183 @@ specVersion: 0.0.2
     let filter = data_source.mapping.block_handlers[0].filter.clone();
     let required_capabilities = NodeCapabilities::from_data_sources(&onchain_data_sources);
 
-    assert_eq!(BlockHandlerFilter::Call, filter.unwrap());
+    assert_eq!(BlockHandlerFilter::call(vec![]), filter.unwrap());
     assert_eq!(true, required_capabilities.traces);
     assert_eq!("Qmmanifest", manifest.id.as_str());
 }
 
+#[tokio::test]
+async fn parse_block_handlers_with_call_filter_functions() {
+    let yaml = "
+dataSources:
+  - kind: ethereum/contract
+    name: Factory
+    network: mainnet
+    source:
+      address: \"0x0000000000000000000000000000000000000000\"
+      abi: Factory
+      startBlock: 9562480
+    mapping:
+      kind: ethereum/events
+      apiVersion: 0.0.4
+      language: wasm/assemblyscript
+      entities:
+        - TestEntity
+      file:
+        /: /ipfs/Qmmapping
+      abis:
+        - name: Factory
+          file:
+            /: /ipfs/Qmabi
+      blockHandlers:
+        - handler: handleBlock
+          filter:
+            kind: call
+            functions:
+              - FUNCTION
+schema:
+  file:
+    /: /ipfs/Qmschema
+specVersion: 1.3.0
+";
+
+    let manifest = resolve_manifest(
+        &yaml.replace("FUNCTION", "get(uint256)"),
+        SPEC_VERSION_1_3_0,
+    )
+    .await;
+    let data_source = manifest.data_sources[0].as_onchain().unwrap();
+    let filter = data_source.mapping.block_handlers[0].filter.clone();
+
+    assert_eq!(
+        BlockHandlerFilter::call(vec!["get(uint256)".to_string()]),
+        filter.unwrap()
+    );
+    assert!(data_source.validate(&LATEST_VERSION).is_empty());
+
+    let manifest = resolve_manifest(
+        &yaml.replace("FUNCTION", "set(uint256)"),
+        SPEC_VERSION_1_3_0,
+    )
+    .await;
+    let data_source = manifest.data_sources[0].as_onchain().unwrap();
+    let errors = data_source.validate(&LATEST_VERSION);
+
+    assert_eq!(1, errors.len());
+    assert!(errors[0]
+        .to_string()
+        .contains("function `set(uint256)` not found in contract `Factory`"));
+}
+
+#[tokio::test]
+async fn parse_end_block_handler() {
+    let yaml = "
+dataSources:
+  - kind: ethereum/contract
+    name: Factory
+    network: mainnet
+    source:
+      address: \"0x0000000000000000000000000000000000000000\"
+      abi: Factory
+      startBlock: 9562480
+END_BLOCK
+    mapping:
+      kind: ethereum/events
+      apiVersion: 0.0.4
+      language: wasm/assemblyscript
+      entities:
+        - TestEntity
+      file:
+        /: /ipfs/Qmmapping
+      abis:
+        - name: Factory
+          file:
+            /: /ipfs/Qmabi
+      endBlockHandler: handleEnd
+schema:
+  file:
+    /: /ipfs/Qmschema
+specVersion: 1.3.0
+";
+
+    let manifest = resolve_manifest(
+        &yaml.replace("END_BLOCK", "      endBlock: 9562500"),
+        SPEC_VERSION_1_3_0,
+    )
+    .await;
+    let data_source = manifest.data_sources[0].as_onchain().unwrap();
+
+    assert_eq!(
+        Some("handleEnd"),
+        data_source.mapping.end_block_handler.as_deref()
+    );
+    assert!(data_source.validate(&LATEST_VERSION).is_empty());
+
+    let manifest = resolve_manifest(&yaml.replace("END_BLOCK\n", ""), SPEC_VERSION_1_3_0).await;
+    let data_source = manifest.data_sources[0].as_onchain().unwrap();
+    let errors = data_source.validate(&LATEST_VERSION);
+
+    assert_eq!(1, errors.len());
+    assert!(errors[0]
+        .to_string()
+        .contains("`endBlockHandler` but no `endBlock`"));
+}
+
+#[tokio::test]
+async fn end_block_handler_in_template_is_rejected() {
+    const YAML: &str = "
+dataSources: []
+templates:
+  - kind: ethereum/contract
+    name: Factory
+    network: mainnet
+    source:
+      abi: Factory
+    mapping:
+      kind: ethereum/events
+      apiVersion: 0.0.4
+      language: wasm/assemblyscript
+      entities:
+        - TestEntity
+      file:
+        /: /ipfs/Qmmapping
+      abis:
+        - name: Factory
+          file:
+            /: /ipfs/Qmabi
+      endBlockHandler: handleEnd
+schema:
+  file:
+    /: /ipfs/Qmschema
+specVersion: 1.3.0
+";
+
+    let mut resolver = TextResolver::default();
+    let id = DeploymentHash::new("Qmmanifest").unwrap();
+
+    resolver.add(id.as_str(), &YAML);
+    resolver.add("/ipfs/Qmschema", &GQL_SCHEMA);
+    resolver.add("/ipfs/Qmabi", &ABI);
+    resolver.add("/ipfs/Qmmapping", &MAPPING_WITH_IPFS_FUNC_WASM);
+
+    let resolver: Arc<dyn LinkResolverTrait> = Arc::new(resolver);
+
+    let raw = serde_yaml::from_str(YAML).unwrap();
+    let err = SubgraphManifest::<Chain>::resolve_from_raw(
+        id,
+        raw,
+        &resolver,
+        &LOGGER,
+        SPEC_VERSION_1_3_0,
+    )
+    .await
+    .unwrap_err();
+
+    assert!(err
+        .to_string()
+        .contains("data source template `Factory` has an `endBlockHandler`"));
+}
+
 #[tokio::test]
 async fn parse_block_handlers_with_once_filter() {
     const YAML: &str = "
@@ -1375,6 +1548,59 @@ schema:
     });
 }
 
+#[tokio::test]
+async fn wildcard_event_handlers_require_feature() {
+    let yaml = "
+specVersion: SPEC_VERSION
+schema:
+  file:
+    /: /ipfs/Qmschema
+features:
+  - ipfsOnEthereumContracts
+dataSources:
+  - kind: ethereum/contract
+    name: Factory
+    network: mainnet
+    source:
+      abi: Factory
+      startBlock: 9562480
+    mapping:
+      kind: ethereum/events
+      apiVersion: 0.0.4
+      language: wasm/assemblyscript
+      entities:
+        - TestEntity
+      file:
+        /: /ipfs/Qmmapping
+      abis:
+        - name: Factory
+          file:
+            /: /ipfs/Qmabi
+      eventHandlers:
+        - event: Created(address)
+          handler: handleCreated
+";
+
+    // Subgraphs from before the feature existed don't have to declare it
+    let manifest = resolve_manifest(
+        &yaml.replace("SPEC_VERSION", "1.2.0"),
+        LATEST_VERSION.clone(),
+    )
+    .await;
+    assert!(validate_subgraph_features(&manifest).is_ok());
+
+    let manifest = resolve_manifest(
+        &yaml.replace("SPEC_VERSION", "1.3.0"),
+        LATEST_VERSION.clone(),
+    )
+    .await;
+    let err = validate_subgraph_features(&manifest).unwrap_err();
+    assert_eq!(
+        "The feature `wildcardEvents` is used by the subgraph but it is not declared in the manifest.",
+        err.to_string()
+    );
+}
+
 #[test]
 fn parses_eth_call_decls() {
     const YAML: &str = "
@@ -1445,3 +1671,101 @@ dataSources:
         assert_eq!(4, decls.len());
     });
 }
+
+#[test]
+fn validates_file_sources() {
+    const YAML: &str = "
+specVersion: 1.3.0
+schema:
+  file:
+    /: /ipfs/Qmschema
+features:
+  - ipfsOnEthereumContracts
+dataSources:
+  - kind: ethereum/contract
+    name: Token
+    network: mainnet
+    source:
+      address: \"0x0000000000000000000000000000000000000000\"
+      abi: Token
+      startBlock: 9562480
+    mapping:
+      kind: ethereum/events
+      apiVersion: 0.0.7
+      language: wasm/assemblyscript
+      entities:
+        - TestEntity
+      file:
+        /: /ipfs/Qmmapping
+      abis:
+        - name: Token
+          file:
+            /: /ipfs/Qmabi
+      eventHandlers:
+        - event: MetadataSet(indexed uint256,bytes32)
+          handler: handleMetadataSet
+          fileSources:
+            - template: TEMPLATE
+              param: PARAM
+              transform: cidv0FromBytes32
+templates:
+  - name: Metadata
+    kind: file/ipfs
+    mapping:
+      apiVersion: 0.0.7
+      language: wasm/assemblyscript
+      entities:
+        - TestEntity
+      file:
+        /: /ipfs/Qmmapping
+      handler: handleMetadata
+";
+    const EVENT_ABI: &str = "[{\"type\":\"event\",\"name\":\"MetadataSet\",\"anonymous\":false,\"inputs\":[{\"name\":\"id\",\"type\":\"uint256\",\"indexed\":true},{\"name\":\"digest\",\"type\":\"bytes32\",\"indexed\":false}]}]";
+
+    test_store::run_test_sequentially(|store| async move {
+        let store = store.subgraph_store();
+        let validate = |template: &str, param: &str| {
+            let store = store.clone();
+            let yaml = YAML.replace("TEMPLATE", template).replace("PARAM", param);
+            async move {
+                let mut resolver = TextResolver::default();
+                let id = DeploymentHash::new("Qmmanifest").unwrap();
+                resolver.add(id.as_str(), &yaml);
+                resolver.add("/ipfs/Qmabi", &EVENT_ABI);
+                resolver.add("/ipfs/Qmschema", &GQL_SCHEMA);
+                resolver.add("/ipfs/Qmmapping", &MAPPING_WITH_IPFS_FUNC_WASM);
+
+                let resolver: Arc<dyn LinkResolverTrait> = Arc::new(resolver);
+
+                let raw = serde_yaml::from_str(&yaml).unwrap();
+                let unvalidated: UnvalidatedSubgraphManifest<Chain> =
+                    UnvalidatedSubgraphManifest::resolve(
+                        id,
+                        raw,
+                        &resolver,
+                        &LOGGER,
+                        SPEC_VERSION_1_3_0.clone(),
+                    )
+                    .await
+                    .expect("Parsing simple manifest works");
+                unvalidated.validate(store, true).await
+            }
+        };
+
+        let manifest = validate("Metadata", "digest").await.unwrap();
+        let ds = manifest.data_sources[0].as_onchain().unwrap();
+        assert_eq!(1, ds.mapping.event_handlers[0].file_sources.len());
+
+        let errors = validate("Missing", "digest").await.unwrap_err();
+        assert_eq!(1, errors.len());
+        assert!(errors[0]
+            .to_string()
+            .contains("file source template `Missing` is not a file data source template"));
+
+        let errors = validate("Metadata", "id").await.unwrap_err();
+        assert_eq!(1, errors.len());
+        assert!(errors[0]
+            .to_string()
+            .contains("can not be used with the `Cidv0FromBytes32` transform"));
+    });
+}
@@ -13,9 +13,11 @@ use graph::prelude::web3::types::H256;
 use graph::prelude::{anyhow::anyhow, anyhow::Error};
 use graph::prelude::{serde_json as json, EthereumBlock};
 use graph::prelude::{BlockNumber, QueryStoreManager, QueryTarget};
+use graph::prometheus::Counter;
 use graph::{cheap_clone::CheapClone, prelude::web3::types::H160};
 use graph::{components::store::BlockStore as _, prelude::DeploymentHash};
 use graph::{components::store::ChainStore as _, prelude::EthereumCallCache as _};
+use graph_chain_ethereum::BufferedCallCache;
 use graph_store_postgres::Store as DieselStore;
 use graph_store_postgres::{layout_for_tests::FAKE_NETWORK_SHARED, ChainStore as DieselChainStore};
 
@@ -487,6 +489,109 @@ fn eth_call_cache() {
     })
 }
 
+#[test]
+fn eth_call_cache_set_calls() {
+    let chain = vec![&*GENESIS_BLOCK, &*BLOCK_ONE];
+
+    run_test(chain, |store, _| {
+        let logger = LOGGER.cheap_clone();
+        let address = H160([2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2]);
+        let call = |data: u8| call::Request::new(address, vec![data], 0);
+        let value = |data: u8| call::Retval::Value(Bytes::from([data].as_slice()));
+        let get = |data: u8| {
+            store
+                .get_call(&call(data), BLOCK_ONE.block_ptr())
+                .unwrap()
+                .map(|resp| resp.retval.unwrap().as_slice().to_vec())
+        };
+
+        // Two index nodes flush overlapping calls for the same block; the
+        // entries that were written first are kept
+        store
+            .set_calls(
+                &logger,
+                vec![(call(1), value(11)), (call(2), value(12))],
+                BLOCK_ONE.block_ptr(),
+            )
+            .unwrap();
+        store
+            .set_calls(
+                &logger,
+                vec![
+                    (call(2), value(22)),
+                    (call(3), value(23)),
+                    (call(3), value(23)),
+                    (call(4), call::Retval::Null),
+                ],
+                BLOCK_ONE.block_ptr(),
+            )
+            .unwrap();
+
+        assert_eq!(Some(vec![11]), get(1));
+        assert_eq!(Some(vec![12]), get(2));
+        assert_eq!(Some(vec![23]), get(3));
+        assert_eq!(None, get(4));
+
+        let ret = store.get_call(&call(1), GENESIS_BLOCK.block_ptr()).unwrap();
+        assert!(ret.is_none());
+
+        Ok(())
+    })
+}
+
+#[test]
+fn eth_call_cache_sources() {
+    let chain = vec![&*GENESIS_BLOCK, &*BLOCK_ONE];
+
+    run_test(chain, |store, _| {
+        let logger = LOGGER.cheap_clone();
+        let address = H160([3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3]);
+        let call = |data: u8| call::Request::new(address, vec![data], 0);
+        store
+            .set_calls(
+                &logger,
+                vec![
+                    (call(1), call::Retval::Value(Bytes::from([11].as_slice()))),
+                    (call(2), call::Retval::Value(Bytes::from([12].as_slice()))),
+                ],
+                BLOCK_ONE.block_ptr(),
+            )
+            .unwrap();
+        let sources = |resps: Vec<call::Response>| {
+            resps
+                .into_iter()
+                .map(|resp| resp.source.as_str())
+                .collect::<Vec<_>>()
+        };
+
+        // Calls answered by the chain store come from the store
+        let resp = store.get_call(&call(1), BLOCK_ONE.block_ptr()).unwrap();
+        assert_eq!(Some(call::Source::Store), resp.map(|resp| resp.source));
+        let (resps, missing) = store
+            .get_calls(&[call(1), call(2), call(3)], BLOCK_ONE.block_ptr())
+            .unwrap();
+        assert_eq!(vec!["store", "store"], sources(resps));
+        assert_eq!(vec![call(3)], missing);
+
+        // A buffered cache answers calls it has seen in the block from
+        // memory, and the others from the store
+        let buffered = BufferedCallCache::new(
+            store.cheap_clone(),
+            Counter::new("flush_failures", "flush failures").unwrap(),
+            logger,
+        );
+        let resp = buffered.get_call(&call(1), BLOCK_ONE.block_ptr()).unwrap();
+        assert_eq!(Some(call::Source::Store), resp.map(|resp| resp.source));
+        let (resps, missing) = buffered
+            .get_calls(&[call(1), call(2), call(3)], BLOCK_ONE.block_ptr())
+            .unwrap();
+        assert_eq!(vec!["memory", "store"], sources(resps));
+        assert_eq!(vec![call(3)], missing);
+
+        Ok(())
+    })
+}
+
 #[test]
 /// Tests only query correctness. No data is involved.
 fn test_transaction_receipts_in_block_function() {
@@ -1128,9 +1128,10 @@ fn mock_data_source() -> graph_chain_ethereum::DataSource {
             language: String::from("wasm/assemblyscript"),
             entities: vec![],
             abis: vec![],
-            event_handlers: vec![],
+            event_handlers: Default::default(),
             call_handlers: vec![],
             block_handlers: vec![],
+            end_block_handler: None,
             link: Link {
                 link: "link".to_owned(),
             },
@@ -1143,9 +1144,9 @@ fn mock_data_source() -> graph_chain_ethereum::DataSource {
 }
 
 fn mock_abi() -> MappingABI {
-    MappingABI {
-        name: "mock_abi".to_string(),
-        contract: Contract::load(
+    MappingABI::new(
+        "mock_abi".to_string(),
+        Contract::load(
             r#"[
             {
                 "inputs": [
@@ -1160,7 +1161,7 @@ fn mock_abi() -> MappingABI {
             .as_bytes(),
         )
         .unwrap(),
-    }
+    )
 }
 
 #[test]
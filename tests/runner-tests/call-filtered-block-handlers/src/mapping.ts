@@ -0,0 +1,17 @@
+import { ethereum } from '@graphprotocol/graph-ts';
+import { BlockCall } from '../generated/schema';
+
+function saveBlockCall(handler: string, block: ethereum.Block): void {
+  let entity = new BlockCall(handler + '-' + block.number.toString());
+  entity.handler = handler;
+  entity.number = block.number;
+  entity.save();
+}
+
+export function handleBlockA(block: ethereum.Block): void {
+  saveBlockCall('A', block);
+}
+
+export function handleBlockB(block: ethereum.Block): void {
+  saveBlockCall('B', block);
+}
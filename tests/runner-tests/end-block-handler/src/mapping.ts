@@ -0,0 +1,14 @@
+import { BigInt, ethereum } from '@graphprotocol/graph-ts';
+import { EndBlockCall } from '../generated/schema';
+
+export function handleEnd(block: ethereum.Block): void {
+  let entity = EndBlockCall.load('end');
+  if (entity == null) {
+    entity = new EndBlockCall('end');
+    entity.count = BigInt.fromI32(0);
+  }
+  entity.count = entity.count.plus(BigInt.fromI32(1));
+  entity.number = block.number;
+  entity.hash = block.hash.toHexString();
+  entity.save();
+}
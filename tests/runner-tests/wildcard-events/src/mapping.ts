@@ -0,0 +1,11 @@
+import { Transfer as TransferEvent } from '../generated/Token/Token';
+import { Transfer } from '../generated/schema';
+
+export function handleTransfer(event: TransferEvent): void {
+  let transfer = new Transfer(event.address.toHexString());
+  transfer.token = event.address;
+  transfer.from = event.params.from;
+  transfer.to = event.params.to;
+  transfer.value = event.params.value;
+  transfer.save();
+}
@@ -151,6 +151,31 @@ pub fn push_test_log(block: &mut BlockWithTriggers<Chain>, payload: impl Into<St
         .push(EthereumTrigger::Log(LogRef::FullLog(log, None)))
 }
 
+/// Pushes a log emitted by `address` with the given `topics` and `data`
+pub fn push_test_log_from(
+    block: &mut BlockWithTriggers<Chain>,
+    address: Address,
+    topics: Vec<H256>,
+    data: Vec<u8>,
+) {
+    let log = Arc::new(Log {
+        address,
+        topics,
+        data: data.into(),
+        block_hash: Some(H256::from_slice(block.ptr().hash.as_slice())),
+        block_number: Some(block.ptr().number.into()),
+        transaction_hash: Some(H256::from_low_u64_be(0)),
+        transaction_index: Some(0.into()),
+        log_index: Some(0.into()),
+        transaction_log_index: Some(0.into()),
+        log_type: None,
+        removed: None,
+    });
+    block
+        .trigger_data
+        .push(EthereumTrigger::Log(LogRef::FullLog(log, None)))
+}
+
 pub fn push_test_command(
     block: &mut BlockWithTriggers<Chain>,
     test_command: impl Into<String>,
@@ -184,3 +209,26 @@ pub fn push_test_polling_trigger(block: &mut BlockWithTriggers<Chain>) {
         EthereumBlockTriggerType::End,
     ))
 }
+
+pub fn push_test_data_source_end_trigger(block: &mut BlockWithTriggers<Chain>) {
+    block.trigger_data.push(EthereumTrigger::Block(
+        block.ptr(),
+        EthereumBlockTriggerType::DataSourceEnd,
+    ))
+}
+
+/// Pushes a block trigger for calls to `Address::zero()` with the
+/// functions given by their full signature
+pub fn push_test_call_filter_trigger(block: &mut BlockWithTriggers<Chain>, functions: &[&str]) {
+    let selectors = functions
+        .iter()
+        .map(|function| {
+            let hash = tiny_keccak::keccak256(function.as_bytes());
+            [hash[0], hash[1], hash[2], hash[3]]
+        })
+        .collect();
+    block.trigger_data.push(EthereumTrigger::Block(
+        block.ptr(),
+        EthereumBlockTriggerType::WithCallTo(Address::zero(), selectors),
+    ))
+}
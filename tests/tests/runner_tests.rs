@@ -19,10 +19,12 @@ use graph::object;
 use graph::prelude::ethabi::ethereum_types::H256;
 use graph::prelude::web3::types::Address;
 use graph::prelude::{
-    hex, CheapClone, DeploymentHash, SubgraphAssignmentProvider, SubgraphName, SubgraphStore,
+    ethabi, hex, tiny_keccak, CheapClone, DeploymentHash, SubgraphAssignmentProvider, SubgraphName,
+    SubgraphStore,
 };
 use graph_tests::fixture::ethereum::{
-    chain, empty_block, generate_empty_blocks_for_range, genesis, push_test_command, push_test_log,
+    chain, empty_block, generate_empty_blocks_for_range, genesis, push_test_call_filter_trigger,
+    push_test_command, push_test_data_source_end_trigger, push_test_log, push_test_log_from,
     push_test_polling_trigger,
 };
 
@@ -628,6 +630,157 @@ async fn end_block() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn end_block_handler() {
+    let RunnerTestRecipe { stores, test_info } =
+        RunnerTestRecipe::new("end_block_handler", "end-block-handler").await;
+
+    // Every block from the end block on carries an end trigger, so that
+    // the handler could run again whenever the subgraph is restarted
+    let blocks = {
+        let block_0 = genesis();
+        let block_1_to_4 = generate_empty_blocks_for_range(block_0.ptr(), 1, 4, 0);
+        let mut block_5_to_10 =
+            generate_empty_blocks_for_range(block_1_to_4.last().unwrap().ptr(), 5, 10, 0);
+        for block in block_5_to_10.iter_mut() {
+            push_test_data_source_end_trigger(block);
+        }
+
+        vec![block_0]
+            .into_iter()
+            .chain(block_1_to_4)
+            .chain(block_5_to_10)
+            .collect()
+    };
+
+    let chain = chain(&test_info.test_name, blocks, &stores, None).await;
+    let ctx = fixture::setup(&test_info, &stores, &chain, None, None).await;
+
+    let query = r#"{ endBlockCalls { id count number hash } }"#;
+    let expected = Some(object! {
+        endBlockCalls: vec![object! {
+            id: "end",
+            count: "1",
+            number: "5",
+            hash: "0x0000000000000000000000000000000000000000000000000000000000000005"
+        }]
+    });
+
+    // Stop right at the end block, the handler has run once
+    ctx.start_and_sync_to(test_ptr(5)).await;
+    assert_eq!(ctx.query(query).await.unwrap(), expected);
+
+    // Restart from before the end block. The end block is processed again,
+    // but what the handler did the first time was reverted with it
+    ctx.rewind(test_ptr(4));
+    ctx.start_and_sync_to(test_ptr(5)).await;
+    assert_eq!(ctx.query(query).await.unwrap(), expected);
+
+    // Restart and sync past the end block, the handler does not run again
+    ctx.start_and_sync_to(test_ptr(10)).await;
+    assert_eq!(ctx.query(query).await.unwrap(), expected);
+}
+
+#[tokio::test]
+async fn call_filtered_block_handlers() {
+    let RunnerTestRecipe { stores, test_info } = RunnerTestRecipe::new(
+        "call_filtered_block_handlers",
+        "call-filtered-block-handlers",
+    )
+    .await;
+
+    // Both data sources are on the same address, `ContractA` only wants
+    // blocks that call `setA` and `ContractB` only those that call `setB`
+    let blocks = {
+        let block_0 = genesis();
+        let mut block_1 = empty_block(block_0.ptr(), test_ptr(1));
+        push_test_call_filter_trigger(&mut block_1, &["setA(uint256)"]);
+        let mut block_2 = empty_block(block_1.ptr(), test_ptr(2));
+        push_test_call_filter_trigger(&mut block_2, &["setB(uint256)"]);
+        let mut block_3 = empty_block(block_2.ptr(), test_ptr(3));
+        push_test_call_filter_trigger(&mut block_3, &["setA(uint256)", "setB(uint256)"]);
+        let block_4 = empty_block(block_3.ptr(), test_ptr(4));
+        vec![block_0, block_1, block_2, block_3, block_4]
+    };
+
+    let chain = chain(&test_info.test_name, blocks, &stores, None).await;
+    let ctx = fixture::setup(&test_info, &stores, &chain, None, None).await;
+    ctx.start_and_sync_to(test_ptr(4)).await;
+
+    let query_res = ctx
+        .query(r#"{ blockCalls(orderBy: id) { id handler number } }"#)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        query_res,
+        Some(object! {
+            blockCalls: vec![
+                object! { id: "A-1", handler: "A", number: "1" },
+                object! { id: "A-3", handler: "A", number: "3" },
+                object! { id: "B-2", handler: "B", number: "2" },
+                object! { id: "B-3", handler: "B", number: "3" },
+            ]
+        })
+    );
+}
+
+#[tokio::test]
+async fn wildcard_events() {
+    let RunnerTestRecipe { stores, test_info } =
+        RunnerTestRecipe::new("wildcard_events", "wildcard-events").await;
+
+    let erc20 = Address::from_low_u64_be(20);
+    let erc721 = Address::from_low_u64_be(721);
+    let blocks = {
+        let block_0 = genesis();
+        let mut block_1 = empty_block(block_0.ptr(), test_ptr(1));
+
+        // ERC-20 and ERC-721 `Transfer` events have the same topic0, but
+        // ERC-721 indexes the token id, so it can't be decoded as ERC-20
+        let topic0 = H256::from(tiny_keccak::keccak256(b"Transfer(address,address,uint256)"));
+        let from = H256::from(Address::from_low_u64_be(1));
+        let to = H256::from(Address::from_low_u64_be(2));
+        let value = ethabi::encode(&[ethabi::Token::Uint(100.into())]);
+        push_test_log_from(&mut block_1, erc20, vec![topic0, from, to], value);
+        let token_id = H256::from_low_u64_be(7);
+        push_test_log_from(
+            &mut block_1,
+            erc721,
+            vec![topic0, from, to, token_id],
+            vec![],
+        );
+
+        let block_2 = empty_block(block_1.ptr(), test_ptr(2));
+        vec![block_0, block_1, block_2]
+    };
+
+    let chain = chain(&test_info.test_name, blocks, &stores, None).await;
+    let ctx = fixture::setup(&test_info, &stores, &chain, None, None).await;
+    ctx.start_and_sync_to(test_ptr(2)).await;
+
+    // Only the ERC-20 transfer is handled, and the ERC-721 transfer does not
+    // fail the subgraph
+    let query_res = ctx
+        .query(r#"{ transfers { id token from to value } }"#)
+        .await
+        .unwrap();
+    assert_eq!(
+        query_res,
+        Some(object! {
+            transfers: vec![object! {
+                id: format!("{:?}", erc20),
+                token: format!("{:?}", erc20),
+                from: format!("{:?}", Address::from_low_u64_be(1)),
+                to: format!("{:?}", Address::from_low_u64_be(2)),
+                value: "100",
+            }]
+        })
+    );
+    let status = ctx.indexing_status().await;
+    assert!(status.health == SubgraphHealth::Healthy);
+}
+
 #[tokio::test]
 async fn file_data_sources() {
     let RunnerTestRecipe { stores, test_info } =